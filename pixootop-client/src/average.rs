@@ -1,5 +1,3 @@
-use crate::PROGRESS_RANGE;
-
 pub struct Averaged<T, const N: usize> {
     data: [T; N],
     idx: usize,
@@ -17,12 +15,10 @@ where
         }
     }
 
-    pub fn next(&mut self, data: T, scale: f64) -> u8 {
+    pub fn next(&mut self, data: T) -> f64 {
         self.data[self.idx] = data;
         self.idx += 1;
         self.idx %= N;
-        (self.data.iter().copied().map(|d| f64::from(d)).sum::<f64>() / N as f64 / scale
-            * PROGRESS_RANGE)
-            .round() as u8
+        self.data.iter().copied().map(|d| f64::from(d)).sum::<f64>() / N as f64
     }
 }
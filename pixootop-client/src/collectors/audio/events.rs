@@ -0,0 +1,124 @@
+use std::{
+    cell::RefCell,
+    rc::Rc,
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
+};
+
+use anyhow::{Context as _, Result, bail};
+use libpulse_binding::{
+    context::{
+        self, Context,
+        subscribe::{Facility, InterestMaskSet},
+    },
+    mainloop::threaded::Mainloop,
+};
+
+use super::Device;
+
+/// Subscribes to change events of sinks or sources, which works with both pulse and PipeWire,
+/// so the volume only has to be read again after it changed.
+pub struct Events {
+    mainloop: Rc<RefCell<Mainloop>>,
+    context: Rc<RefCell<Context>>,
+    changed: Arc<AtomicBool>,
+}
+
+impl Events {
+    pub fn subscribe(device: Device) -> Result<Self> {
+        let mainloop = Rc::new(RefCell::new(
+            Mainloop::new().context("creating pulse mainloop")?,
+        ));
+        let context = Rc::new(RefCell::new(
+            Context::new(&*mainloop.borrow(), "pixootop").context("creating pulse context")?,
+        ));
+        // wake up the connecting thread below once connected or failed. The callback runs on the
+        // mainloop thread while that thread holds borrows, hence the raw pointers as in
+        // libpulse-binding's own examples.
+        let (mainloop_clone, context_clone) = (Rc::clone(&mainloop), Rc::clone(&context));
+        context
+            .borrow_mut()
+            .set_state_callback(Some(Box::new(move || {
+                match unsafe { (*context_clone.as_ptr()).get_state() } {
+                    context::State::Ready | context::State::Failed | context::State::Terminated => {
+                        unsafe { (*mainloop_clone.as_ptr()).signal(false) };
+                    }
+                    _ => {}
+                }
+            })));
+        context
+            .borrow_mut()
+            .connect(None, context::FlagSet::NOFLAGS, None)
+            .context("connecting to pulse")?;
+
+        let mut events = Self {
+            mainloop,
+            context,
+            changed: Arc::new(AtomicBool::new(true)),
+        };
+        events.mainloop.borrow_mut().lock();
+        let result = events.start(device);
+        events.mainloop.borrow_mut().unlock();
+        result.map(|()| events)
+    }
+
+    /// Waits for the context to connect and subscribes to the device's facility. Must be called
+    /// with the mainloop locked.
+    fn start(&mut self, device: Device) -> Result<()> {
+        self.mainloop
+            .borrow_mut()
+            .start()
+            .context("starting pulse mainloop")?;
+        loop {
+            match self.context.borrow().get_state() {
+                context::State::Ready => break,
+                context::State::Failed | context::State::Terminated => {
+                    bail!("connecting to pulse failed")
+                }
+                _ => {}
+            }
+            self.mainloop.borrow_mut().wait();
+        }
+        self.context.borrow_mut().set_state_callback(None);
+
+        // changing the default device is an event on the server
+        let (facility, mask) = match device {
+            Device::Sink => (Facility::Sink, InterestMaskSet::SINK),
+            Device::Source => (Facility::Source, InterestMaskSet::SOURCE),
+        };
+        let changed = Arc::clone(&self.changed);
+        let mut context = self.context.borrow_mut();
+        context.set_subscribe_callback(Some(Box::new(move |event_facility, _, _| {
+            if event_facility.is_some_and(|event| event == facility || event == Facility::Server) {
+                changed.store(true, Ordering::Relaxed);
+            }
+        })));
+        context.subscribe(mask | InterestMaskSet::SERVER, |_| {});
+        Ok(())
+    }
+
+    /// Whether events still arrive. Changes are missed once the connection is lost, e.g. when
+    /// the sound server restarts.
+    pub fn connected(&self) -> bool {
+        self.mainloop.borrow_mut().lock();
+        let state = self.context.borrow().get_state();
+        self.mainloop.borrow_mut().unlock();
+        state == context::State::Ready
+    }
+
+    /// Whether the device may have changed since the last call
+    pub fn take_changed(&self) -> bool {
+        self.changed.swap(false, Ordering::Relaxed)
+    }
+}
+
+impl Drop for Events {
+    fn drop(&mut self) {
+        self.mainloop.borrow_mut().lock();
+        self.context.borrow_mut().disconnect();
+        self.mainloop.borrow_mut().unlock();
+        self.mainloop.borrow_mut().stop();
+    }
+}
@@ -0,0 +1,112 @@
+use std::time::{Duration, Instant};
+
+use anyhow::Result;
+use log::{debug, info};
+
+use super::{Collector, Sample};
+
+mod events;
+mod pipewire;
+mod pulse;
+
+/// How often the volume is read without change events, as reading it through PipeWire spawns a
+/// process
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Clone, Copy, Default, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Backend {
+    /// PipeWire if it is running, pulse otherwise
+    #[default]
+    Auto,
+    #[serde(rename = "pipewire")]
+    PipeWire,
+    Pulse,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum Device {
+    /// Output, like speakers or headphones
+    Sink,
+    /// Input, like a microphone
+    Source,
+}
+
+#[derive(Clone, Copy)]
+struct Level {
+    /// Volume where 100 is the nominal maximum, which can be exceeded
+    percent: f64,
+    muted: bool,
+}
+
+/// A way to read the volume of an audio device.
+trait AudioBackend {
+    fn level(&mut self) -> Result<Level>;
+}
+
+/// Volume and mute state of the default or a named sink or source.
+pub struct Audio {
+    backend: Box<dyn AudioBackend>,
+    /// Change events, if available, to avoid reading the volume when nothing changed
+    events: Option<events::Events>,
+    /// Latest level and when it was read
+    level: Option<(Level, Instant)>,
+}
+
+impl Audio {
+    pub fn new(device: Device, backend: Backend, name: Option<String>) -> Result<Self> {
+        let backend: Box<dyn AudioBackend> = match backend {
+            Backend::Auto => match pipewire::PipeWire::new(device, name.clone()) {
+                Ok(pipewire) => Box::new(pipewire),
+                Err(err) => {
+                    debug!("not using PipeWire: {err:#}");
+                    info!("using pulse for {device:?} volume");
+                    Box::new(pulse::Pulse::new(device, name)?)
+                }
+            },
+            Backend::PipeWire => Box::new(pipewire::PipeWire::new(device, name)?),
+            Backend::Pulse => Box::new(pulse::Pulse::new(device, name)?),
+        };
+        let events = events::Events::subscribe(device)
+            .inspect_err(|err| debug!("polling {device:?} volume: {err:#}"))
+            .ok();
+        Ok(Self {
+            backend,
+            events,
+            level: None,
+        })
+    }
+}
+
+impl Collector for Audio {
+    fn sample(&mut self) -> Result<Sample> {
+        if self
+            .events
+            .as_ref()
+            .is_some_and(|events| !events.connected())
+        {
+            info!("lost connection to pulse, polling the volume");
+            self.events = None;
+            self.level = None;
+        }
+        let changed = match &self.events {
+            Some(events) => events.take_changed(),
+            None => self
+                .level
+                .is_none_or(|(_, time)| time.elapsed() >= POLL_INTERVAL),
+        };
+        let level = match (self.level, changed) {
+            (Some((level, _)), false) => level,
+            _ => {
+                // read again next time if this fails
+                self.level = None;
+                self.level.insert((self.backend.level()?, Instant::now())).0
+            }
+        };
+        Ok(Sample {
+            value: level.percent,
+            muted: Some(level.muted),
+            ..Sample::default()
+        })
+    }
+}
@@ -0,0 +1,88 @@
+use std::process::{Command, Stdio};
+
+use anyhow::{Context as _, Result, bail};
+
+use super::{AudioBackend, Device, Level};
+
+/// Reads the volume through `wpctl`, the command line interface of WirePlumber, PipeWire's
+/// session manager.
+pub struct PipeWire {
+    node: String,
+}
+
+impl PipeWire {
+    pub fn new(device: Device, node: Option<String>) -> Result<Self> {
+        let mut pipewire = Self {
+            node: node.unwrap_or_else(|| {
+                match device {
+                    Device::Sink => "@DEFAULT_AUDIO_SINK@",
+                    Device::Source => "@DEFAULT_AUDIO_SOURCE@",
+                }
+                .to_string()
+            }),
+        };
+        // fail early so the pulse fallback can be used
+        pipewire.level()?;
+        Ok(pipewire)
+    }
+}
+
+impl AudioBackend for PipeWire {
+    fn level(&mut self) -> Result<Level> {
+        let output = Command::new("wpctl")
+            .args(["get-volume", &self.node])
+            .stdin(Stdio::null())
+            .stderr(Stdio::null())
+            .output()
+            .context("running wpctl")?;
+        if !output.status.success() {
+            bail!("wpctl exited with {}", output.status);
+        }
+        parse(&String::from_utf8_lossy(&output.stdout))
+    }
+}
+
+/// Parses output like `Volume: 0.40` or `Volume: 0.40 [MUTED]`.
+fn parse(output: &str) -> Result<Level> {
+    let mut words = output
+        .trim()
+        .strip_prefix("Volume:")
+        .context("unexpected wpctl output")?
+        .split_whitespace();
+    let volume = words
+        .next()
+        .context("wpctl printed no volume")?
+        .parse::<f64>()
+        .context("parsing wpctl volume")?;
+    Ok(Level {
+        percent: volume * 100.,
+        muted: words.any(|word| word == "[MUTED]"),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_volume() {
+        let level = parse("Volume: 0.40\n").unwrap();
+        assert_eq!(level.percent, 40.);
+        assert!(!level.muted);
+    }
+
+    #[test]
+    fn parses_muted_volume() {
+        let level = parse("Volume: 1.25 [MUTED]\n").unwrap();
+        assert_eq!(level.percent, 125.);
+        assert!(level.muted);
+    }
+
+    #[test]
+    fn rejects_unexpected_output() {
+        assert!(parse("").is_err());
+        assert!(parse("Volume:").is_err());
+        assert!(parse("Volume: loud").is_err());
+        assert!(parse("Translate ID error: '@DEFAULT_AUDIO_SINK@' is not a valid ID").is_err());
+    }
+}
@@ -0,0 +1,39 @@
+use anyhow::{Context as _, Result};
+use libpulse_binding::volume::Volume as PulseVolume;
+use pulsectl::controllers::{DeviceControl, SinkController, SourceController, types::DeviceInfo};
+
+use super::{AudioBackend, Device, Level};
+
+pub struct Pulse {
+    controller: Box<dyn DeviceControl<DeviceInfo>>,
+    name: Option<String>,
+}
+
+impl Pulse {
+    pub fn new(device: Device, name: Option<String>) -> Result<Self> {
+        let controller: Box<dyn DeviceControl<DeviceInfo>> = match device {
+            Device::Sink => Box::new(SinkController::create().context("creating sink controller")?),
+            Device::Source => {
+                Box::new(SourceController::create().context("creating source controller")?)
+            }
+        };
+        Ok(Self { controller, name })
+    }
+}
+
+impl AudioBackend for Pulse {
+    fn level(&mut self) -> Result<Level> {
+        let dev = match &self.name {
+            Some(name) => self.controller.get_device_by_name(name),
+            None => self.controller.get_default_device(),
+        }
+        .context("getting pulse device")?;
+        let avg = dev.volume.avg().0;
+        Ok(Level {
+            percent: (avg - PulseVolume::MUTED.0) as f64
+                / (PulseVolume::NORMAL.0 - PulseVolume::MUTED.0) as f64
+                * 100.,
+            muted: dev.mute,
+        })
+    }
+}
@@ -0,0 +1,379 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context as _, Result};
+
+use super::{Collector, Sample, Scale, Unavailable, Unit};
+
+pub const POWER_SUPPLY_PATH: &str = "/sys/class/power_supply";
+
+#[derive(Debug, Clone, Copy, Default, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Reading {
+    /// Charge in percent
+    #[default]
+    Charge,
+    /// Seconds until the batteries are empty, or full while charging
+    Time,
+    /// Watts the batteries are drained or charged with
+    Power,
+}
+
+/// Charge of the batteries in percent, and whether they are charging, from
+/// `/sys/class/power_supply`. Several batteries are combined by their energy. Can also report
+/// the time remaining or the power draw instead of the charge.
+pub struct Battery {
+    batteries: Vec<PathBuf>,
+    /// Power supplies like `AC` or `ADP1`
+    mains: Vec<PathBuf>,
+    reading: Reading,
+    max: f64,
+}
+
+impl Battery {
+    /// Uses the battery called `name` like `BAT0`, or all batteries if not set. Without `max`,
+    /// a full bar is 10 hours for the time and 50 W for the power.
+    pub fn new(
+        root: &Path,
+        name: Option<&str>,
+        reading: Reading,
+        max: Option<f64>,
+    ) -> Result<Self> {
+        let mut batteries = Vec::new();
+        let mut mains = Vec::new();
+        for entry in fs::read_dir(root)
+            .with_context(|| format!("listing {}", root.display()))?
+            .filter_map(|entry| entry.ok())
+        {
+            let path = entry.path();
+            match read(&path, "type").as_deref() {
+                Some("Battery") if name.is_none_or(|name| entry.file_name() == name) => {
+                    batteries.push(path)
+                }
+                Some("Mains") => mains.push(path),
+                _ => {}
+            }
+        }
+        if batteries.is_empty() {
+            Err(Unavailable("no battery found"))?;
+        }
+        batteries.sort();
+        Ok(Self {
+            batteries,
+            mains,
+            reading,
+            max: max.unwrap_or(match reading {
+                Reading::Charge => 100.,
+                Reading::Time => 10. * 3600.,
+                Reading::Power => 50.,
+            }),
+        })
+    }
+
+    fn percent(&self) -> Result<f64> {
+        let energies = self
+            .batteries
+            .iter()
+            .map(|dir| energy(dir))
+            .collect::<Option<Vec<_>>>();
+        match energies.map(|energies| {
+            energies
+                .iter()
+                .fold((0., 0.), |(now, full), (n, f)| (now + n, full + f))
+        }) {
+            Some((now, full)) if full > 0. => Ok(now / full * 100.),
+            // not all batteries report their energy, so average their capacity instead
+            _ => Ok(self
+                .batteries
+                .iter()
+                .map(|dir| read_number(dir, "capacity").context("reading battery capacity"))
+                .sum::<Result<f64>>()?
+                / self.batteries.len() as f64),
+        }
+    }
+
+    /// Whether any battery is charging, by its status. Only guessed from the mains and the charge
+    /// if a battery doesn't know, as a charge threshold keeps it "Not charging" on mains.
+    fn charging(&self, percent: f64) -> bool {
+        let statuses: Vec<_> = self
+            .batteries
+            .iter()
+            .map(|dir| read(dir, "status"))
+            .collect();
+        if statuses
+            .iter()
+            .any(|status| status.as_deref() == Some("Charging"))
+        {
+            return true;
+        }
+        if statuses.iter().all(|status| {
+            matches!(
+                status.as_deref(),
+                Some("Discharging" | "Full" | "Not charging")
+            )
+        }) {
+            return false;
+        }
+        let on_mains = self
+            .mains
+            .iter()
+            .any(|dir| read(dir, "online").as_deref() == Some("1"));
+        on_mains && percent < 100.
+    }
+
+    /// Power of all batteries in µW
+    fn power(&self) -> Result<f64> {
+        self.batteries
+            .iter()
+            .map(|dir| power(dir).context("battery reports no power"))
+            .sum()
+    }
+
+    /// Seconds until the batteries are empty, or full while charging
+    fn time(&self, charging: bool) -> Result<f64> {
+        let file = match charging {
+            true => "time_to_full_now",
+            false => "time_to_empty_now",
+        };
+        if let [dir] = self.batteries.as_slice()
+            && let Some(seconds) = read_number(dir, file)
+        {
+            return Ok(seconds);
+        }
+        // the kernel only reports the time for some batteries, so derive it from the energy
+        let mut remaining = 0.;
+        for dir in &self.batteries {
+            let (now, full) = energy_uwh(dir).context("battery reports no energy")?;
+            remaining += match charging {
+                true => full - now,
+                false => now,
+            };
+        }
+        let power = self.power()?;
+        if power <= 0. {
+            return Ok(0.);
+        }
+        Ok(remaining.max(0.) / power * 3600.)
+    }
+}
+
+fn read(dir: &Path, file: &str) -> Option<String> {
+    fs::read_to_string(dir.join(file))
+        .ok()
+        .map(|content| content.trim().to_string())
+}
+
+fn read_number(dir: &Path, file: &str) -> Option<f64> {
+    read(dir, file)?.parse().ok()
+}
+
+/// Current and full energy of a battery, in µWh or µAh depending on what the battery reports
+fn energy(dir: &Path) -> Option<(f64, f64)> {
+    ["energy", "charge"].into_iter().find_map(|kind| {
+        Some((
+            read_number(dir, &format!("{kind}_now"))?,
+            read_number(dir, &format!("{kind}_full"))?,
+        ))
+    })
+}
+
+/// Current and full energy of a battery in µWh, converting a charge in µAh with the voltage
+fn energy_uwh(dir: &Path) -> Option<(f64, f64)> {
+    if let (Some(now), Some(full)) = (
+        read_number(dir, "energy_now"),
+        read_number(dir, "energy_full"),
+    ) {
+        return Some((now, full));
+    }
+    let voltage = read_number(dir, "voltage_now")? / 1e6;
+    Some((
+        read_number(dir, "charge_now")? * voltage,
+        read_number(dir, "charge_full")? * voltage,
+    ))
+}
+
+/// Power a battery is drained or charged with in µW, from the current in µA and the voltage in
+/// µV if it doesn't report the power. Some batteries report a negative value while discharging.
+fn power(dir: &Path) -> Option<f64> {
+    let power = match read_number(dir, "power_now") {
+        Some(power) => power,
+        None => read_number(dir, "current_now")? * read_number(dir, "voltage_now")? / 1e6,
+    };
+    Some(power.abs())
+}
+
+impl Collector for Battery {
+    fn unit(&self) -> Unit {
+        match self.reading {
+            Reading::Charge => Unit::Percent,
+            Reading::Time => Unit::Seconds,
+            Reading::Power => Unit::Watts,
+        }
+    }
+
+    fn scale(&self) -> Scale {
+        Scale::max(self.max)
+    }
+
+    fn sample(&mut self) -> Result<Sample> {
+        let percent = self.percent()?;
+        let charging = self.charging(percent);
+        Ok(Sample {
+            value: match self.reading {
+                Reading::Charge => percent,
+                Reading::Time => self.time(charging)?,
+                Reading::Power => self.power()? / 1e6,
+            },
+            charging: Some(charging),
+            ..Sample::default()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn supply(root: &Path, name: &str, files: &[(&str, &str)]) {
+        let dir = root.join(name);
+        fs::create_dir(&dir).unwrap();
+        for (file, content) in files {
+            fs::write(dir.join(file), format!("{content}\n")).unwrap();
+        }
+    }
+
+    fn battery(root: &Path, reading: Reading) -> Battery {
+        Battery::new(root, None, reading, None).unwrap()
+    }
+
+    #[test]
+    fn combines_batteries_by_energy() {
+        let root = tempfile::tempdir().unwrap();
+        let root = root.path();
+        supply(
+            root,
+            "BAT0",
+            &[
+                ("type", "Battery"),
+                ("status", "Discharging"),
+                ("energy_now", "10000000"),
+                ("energy_full", "40000000"),
+            ],
+        );
+        supply(
+            root,
+            "BAT1",
+            &[
+                ("type", "Battery"),
+                ("status", "Discharging"),
+                ("energy_now", "20000000"),
+                ("energy_full", "20000000"),
+            ],
+        );
+        supply(root, "AC", &[("type", "Mains"), ("online", "0")]);
+
+        let sample = battery(root, Reading::Charge).sample().unwrap();
+        assert_eq!(sample.value, 50.);
+        assert_eq!(sample.charging, Some(false));
+        let only = Battery::new(root, Some("BAT1"), Reading::Charge, None).unwrap();
+        assert_eq!(only.batteries, [root.join("BAT1")]);
+    }
+
+    #[test]
+    fn falls_back_to_capacity_without_full_energy() {
+        let root = tempfile::tempdir().unwrap();
+        supply(
+            root.path(),
+            "BAT0",
+            &[
+                ("type", "Battery"),
+                ("status", "Unknown"),
+                ("energy_now", "0"),
+                ("energy_full", "0"),
+                ("capacity", "64"),
+            ],
+        );
+        supply(root.path(), "AC", &[("type", "Mains"), ("online", "1")]);
+
+        let sample = battery(root.path(), Reading::Charge).sample().unwrap();
+        assert_eq!(sample.value, 64.);
+        assert_eq!(sample.charging, Some(true));
+    }
+
+    #[test]
+    fn trusts_status_below_charge_threshold() {
+        let root = tempfile::tempdir().unwrap();
+        supply(
+            root.path(),
+            "BAT0",
+            &[
+                ("type", "Battery"),
+                ("status", "Not charging"),
+                ("capacity", "80"),
+            ],
+        );
+        supply(root.path(), "AC", &[("type", "Mains"), ("online", "1")]);
+
+        let sample = battery(root.path(), Reading::Charge).sample().unwrap();
+        assert_eq!(sample.value, 80.);
+        assert_eq!(sample.charging, Some(false));
+    }
+
+    #[test]
+    fn derives_power_and_time_from_current_and_charge() {
+        let root = tempfile::tempdir().unwrap();
+        supply(
+            root.path(),
+            "BAT0",
+            &[
+                ("type", "Battery"),
+                ("status", "Discharging"),
+                ("charge_now", "2000000"),
+                ("charge_full", "4000000"),
+                ("current_now", "-1000000"),
+                ("voltage_now", "12000000"),
+            ],
+        );
+
+        let power = battery(root.path(), Reading::Power).sample().unwrap();
+        assert_eq!(power.value, 12.);
+        // 2 Ah at 1 A
+        let time = battery(root.path(), Reading::Time).sample().unwrap();
+        assert!((time.value - 7200.).abs() < 1e-6, "{}", time.value);
+    }
+
+    #[test]
+    fn prefers_time_reported_by_the_kernel() {
+        let root = tempfile::tempdir().unwrap();
+        supply(
+            root.path(),
+            "BAT0",
+            &[
+                ("type", "Battery"),
+                ("status", "Charging"),
+                ("energy_now", "30000000"),
+                ("energy_full", "40000000"),
+                ("power_now", "20000000"),
+                ("time_to_full_now", "1234"),
+            ],
+        );
+
+        let mut time = battery(root.path(), Reading::Time);
+        assert_eq!(time.sample().unwrap().value, 1234.);
+        fs::remove_file(root.path().join("BAT0/time_to_full_now")).unwrap();
+        // 10 Wh left to charge at 20 W
+        assert_eq!(time.sample().unwrap().value, 1800.);
+    }
+
+    #[test]
+    fn is_unavailable_without_battery() {
+        let root = tempfile::tempdir().unwrap();
+        supply(root.path(), "AC", &[("type", "Mains"), ("online", "1")]);
+        let err = Battery::new(root.path(), None, Reading::Charge, None)
+            .err()
+            .unwrap();
+        assert!(err.is::<Unavailable>());
+    }
+}
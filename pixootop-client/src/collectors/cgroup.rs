@@ -0,0 +1,369 @@
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
+
+use anyhow::Result;
+use sysinfo::{CpuRefreshKind, MemoryRefreshKind, RefreshKind, System};
+
+use super::{Collector, Sample, Scale, Unavailable, Unit};
+use crate::{average::Averaged, glob};
+
+const CGROUP_PATH: &str = "/sys/fs/cgroup";
+/// Selects the cgroups of all running containers
+pub const CONTAINERS: &str = "containers";
+/// Names of the cgroups that Docker, Podman, containerd, CRI-O, LXC and systemd-nspawn create for
+/// their containers
+const CONTAINER_PATTERNS: &[&str] = &[
+    "docker-*.scope",
+    "libpod-*.scope",
+    "cri-containerd-*.scope",
+    "crio-*.scope",
+    "lxc.payload.*",
+    "machine-*.scope",
+];
+/// How often the cgroup tree is searched for matching cgroups, as walking it on every sample
+/// is expensive with many services and containers
+const RESCAN_INTERVAL: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Clone, Copy, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Resource {
+    /// CPU time from `cpu.stat`, in percent of all cores
+    Cpu,
+    /// Bytes from `memory.current`
+    Memory,
+    /// Bytes per second read and written from `io.stat`
+    Io,
+}
+
+/// One cgroup selector or a list of them, so a single one can be written as a plain string
+#[derive(Debug, Clone, serde::Deserialize)]
+#[serde(untagged)]
+pub enum Cgroups {
+    One(String),
+    Many(Vec<String>),
+}
+
+impl Cgroups {
+    fn selectors(&self) -> &[String] {
+        match self {
+            Self::One(selector) => std::slice::from_ref(selector),
+            Self::Many(selectors) => selectors,
+        }
+    }
+}
+
+impl Default for Cgroups {
+    fn default() -> Self {
+        Self::One(CONTAINERS.to_string())
+    }
+}
+
+/// CPU time, memory, or IO of all cgroups matching any of the selectors combined. Containers
+/// started later are found within [`RESCAN_INTERVAL`], and stopped ones stop counting at once.
+pub struct Cgroup {
+    cgroups: Cgroups,
+    resource: Resource,
+    max: f64,
+    cores: f64,
+    /// Matching cgroups and when they were searched for
+    matched: Option<(Vec<PathBuf>, Instant)>,
+    /// Counter of each cgroup at the previous sample, and when they were read
+    last: Option<(HashMap<PathBuf, u64>, Instant)>,
+    data: Averaged<f64, 10>,
+}
+
+impl Cgroup {
+    pub fn new(cgroups: Cgroups, resource: Resource, max: Option<f64>) -> Result<Self> {
+        if !Path::new(CGROUP_PATH).join("cgroup.controllers").exists() {
+            Err(Unavailable("cgroup v2 isn't mounted"))?;
+        }
+        let sys = System::new_with_specifics(
+            RefreshKind::nothing()
+                .with_cpu(CpuRefreshKind::nothing())
+                .with_memory(MemoryRefreshKind::nothing().with_ram()),
+        );
+        let max = max.unwrap_or(match resource {
+            Resource::Cpu => 100.,
+            Resource::Memory => sys.total_memory() as f64,
+            Resource::Io => super::default_disk_max(),
+        });
+        Ok(Self {
+            cgroups,
+            resource,
+            max,
+            cores: sys.cpus().len().max(1) as f64,
+            matched: None,
+            last: None,
+            data: Averaged::new(0.),
+        })
+    }
+
+    /// Matching cgroups, searched for again after [`RESCAN_INTERVAL`].
+    fn matching(&mut self) -> &[PathBuf] {
+        if self
+            .matched
+            .as_ref()
+            .is_none_or(|(_, time)| time.elapsed() >= RESCAN_INTERVAL)
+        {
+            self.matched = Some((
+                search(Path::new(CGROUP_PATH), &self.cgroups),
+                Instant::now(),
+            ));
+        }
+        self.matched.as_ref().map_or(&[], |(matched, _)| matched)
+    }
+}
+
+impl Collector for Cgroup {
+    fn unit(&self) -> Unit {
+        match self.resource {
+            Resource::Cpu => Unit::Percent,
+            Resource::Memory => Unit::Bytes,
+            Resource::Io => Unit::BytesPerSecond,
+        }
+    }
+
+    fn scale(&self) -> Scale {
+        Scale::max(self.max)
+    }
+
+    fn sample(&mut self) -> Result<Sample> {
+        // cgroups can disappear after the search, so those that can't be read are skipped
+        let resource = self.resource;
+        let cgroups = self.matching();
+        if let Resource::Memory = resource {
+            let bytes = cgroups
+                .iter()
+                .filter_map(|path| read_number(&path.join("memory.current")))
+                .sum::<u64>();
+            return Ok((bytes as f64).into());
+        }
+
+        let counters = cgroups
+            .iter()
+            .filter_map(|path| {
+                let counter = match resource {
+                    Resource::Cpu => cpu_usage_usec(path),
+                    _ => io_bytes(path),
+                }?;
+                Some((path.clone(), counter))
+            })
+            .collect::<HashMap<_, _>>();
+        let now = Instant::now();
+        // cgroups which are new since the last sample count from now on
+        let delta = match &self.last {
+            Some((last, _)) => counters
+                .iter()
+                .filter_map(|(path, counter)| Some(counter.saturating_sub(*last.get(path)?)))
+                .sum::<u64>(),
+            None => 0,
+        };
+        let elapsed = self
+            .last
+            .replace((counters, now))
+            .map(|(_, time)| now.duration_since(time).as_secs_f64())
+            .filter(|&elapsed| elapsed > 0.);
+        let Some(elapsed) = elapsed else {
+            return Ok(0f64.into());
+        };
+        Ok(match self.resource {
+            Resource::Cpu => (delta as f64 / 1_000_000. / elapsed / self.cores * 100.).into(),
+            _ => self.data.next(delta as f64 / elapsed).into(),
+        })
+    }
+}
+
+/// Directories of all cgroups below `root` matching a selector. Cgroups inside another matching
+/// cgroup are left out, as their usage is already counted in their parent.
+fn search(root: &Path, cgroups: &Cgroups) -> Vec<PathBuf> {
+    let mut matched = Vec::new();
+    for selector in cgroups.selectors() {
+        match selector.as_str() {
+            CONTAINERS => containers(root, &mut matched),
+            _ => expand(root, selector.trim_matches('/'), &mut matched),
+        }
+    }
+    matched.sort();
+    matched.dedup();
+    let mut outermost: Vec<PathBuf> = Vec::new();
+    for path in matched {
+        if !outermost
+            .last()
+            .is_some_and(|parent| path.starts_with(parent))
+        {
+            outermost.push(path);
+        }
+    }
+    outermost
+}
+
+/// Adds the cgroups below `dir` matching `pattern`, a path whose components may contain glob
+/// patterns like `system.slice/docker-*.scope`.
+fn expand(dir: &Path, pattern: &str, matched: &mut Vec<PathBuf>) {
+    let (component, rest) = match pattern.split_once('/') {
+        Some((component, rest)) => (component, Some(rest)),
+        None => (pattern, None),
+    };
+    for child in subdirectories(dir) {
+        let Some(name) = child.file_name().and_then(|name| name.to_str()) else {
+            continue;
+        };
+        if !glob::matches(component, name) {
+            continue;
+        }
+        match rest {
+            Some(rest) => expand(&child, rest, matched),
+            None => matched.push(child),
+        }
+    }
+}
+
+/// Adds all container cgroups below `dir`.
+fn containers(dir: &Path, matched: &mut Vec<PathBuf>) {
+    for child in subdirectories(dir) {
+        let is_container = child
+            .file_name()
+            .and_then(|name| name.to_str())
+            .is_some_and(|name| {
+                // Podman's container monitors aren't part of the container
+                !name.starts_with("libpod-conmon-")
+                    && CONTAINER_PATTERNS
+                        .iter()
+                        .any(|pattern| glob::matches(pattern, name))
+            });
+        match is_container {
+            true => matched.push(child),
+            false => containers(&child, matched),
+        }
+    }
+}
+
+fn subdirectories(dir: &Path) -> impl Iterator<Item = PathBuf> {
+    fs::read_dir(dir)
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_type().is_ok_and(|kind| kind.is_dir()))
+        .map(|entry| entry.path())
+}
+
+fn read_number(path: &Path) -> Option<u64> {
+    fs::read_to_string(path).ok()?.trim().parse().ok()
+}
+
+/// Total CPU time of the cgroup in microseconds.
+fn cpu_usage_usec(cgroup: &Path) -> Option<u64> {
+    // lines like `usage_usec 123456`
+    fs::read_to_string(cgroup.join("cpu.stat"))
+        .ok()?
+        .lines()
+        .find_map(|line| line.strip_prefix("usage_usec ")?.parse().ok())
+}
+
+/// Total bytes read and written by the cgroup on all devices.
+fn io_bytes(cgroup: &Path) -> Option<u64> {
+    // lines like `259:0 rbytes=123 wbytes=456 rios=7 wios=8 dbytes=0 dios=0`
+    let stat = fs::read_to_string(cgroup.join("io.stat")).ok()?;
+    Some(
+        stat.split_whitespace()
+            .filter_map(|field| {
+                let (key, value) = field.split_once('=')?;
+                matches!(key, "rbytes" | "wbytes")
+                    .then(|| value.parse::<u64>().ok())
+                    .flatten()
+            })
+            .sum(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cgroup(path: &Path, files: &[(&str, &str)]) {
+        fs::create_dir_all(path).unwrap();
+        for (file, content) in files {
+            fs::write(path.join(file), content).unwrap();
+        }
+    }
+
+    #[test]
+    fn reads_counters() {
+        let root = tempfile::tempdir().unwrap();
+        let path = root.path();
+        cgroup(
+            path,
+            &[
+                (
+                    "cpu.stat",
+                    "usage_usec 123456\nuser_usec 100000\nsystem_usec 23456\n",
+                ),
+                (
+                    "io.stat",
+                    "259:0 rbytes=100 wbytes=20 rios=7 wios=8 dbytes=5 dios=1\n\
+                     8:0 rbytes=3 wbytes=4 rios=1 wios=1 dbytes=0 dios=0\n",
+                ),
+            ],
+        );
+        assert_eq!(cpu_usage_usec(path), Some(123456));
+        assert_eq!(io_bytes(path), Some(127));
+
+        let empty = path.join("empty");
+        cgroup(&empty, &[("io.stat", "")]);
+        assert_eq!(cpu_usage_usec(&empty), None);
+        assert_eq!(io_bytes(&empty), Some(0));
+    }
+
+    #[test]
+    fn expands_glob_patterns() {
+        let root = tempfile::tempdir().unwrap();
+        let root = root.path();
+        for path in [
+            "system.slice/docker-a.scope",
+            "system.slice/docker-b.scope/inner",
+            "system.slice/sshd.service",
+            "user.slice",
+        ] {
+            cgroup(&root.join(path), &[]);
+        }
+        let mut matched = Vec::new();
+        expand(root, "system.slice/docker-*.scope", &mut matched);
+        matched.sort();
+        assert_eq!(
+            matched,
+            [
+                root.join("system.slice/docker-a.scope"),
+                root.join("system.slice/docker-b.scope"),
+            ]
+        );
+    }
+
+    #[test]
+    fn finds_outermost_containers() {
+        let root = tempfile::tempdir().unwrap();
+        let root = root.path();
+        for path in [
+            "system.slice/docker-a.scope/init.scope",
+            "machine.slice/libpod-conmon-b.scope",
+            "machine.slice/libpod-b.scope",
+            "system.slice/sshd.service",
+        ] {
+            cgroup(&root.join(path), &[]);
+        }
+        let cgroups = Cgroups::Many(vec![
+            CONTAINERS.to_string(),
+            "/system.slice/docker-a.scope/init.scope".to_string(),
+        ]);
+        assert_eq!(
+            search(root, &cgroups),
+            [
+                root.join("machine.slice/libpod-b.scope"),
+                root.join("system.slice/docker-a.scope"),
+            ]
+        );
+    }
+}
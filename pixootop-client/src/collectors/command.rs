@@ -0,0 +1,280 @@
+use std::{
+    io::{BufRead as _, BufReader, Read as _},
+    process::{self, Child, ExitStatus, Stdio},
+    sync::{Arc, Mutex, mpsc},
+    thread,
+    time::{Duration, Instant},
+};
+
+use anyhow::{Context as _, Result, bail};
+use log::debug;
+
+use super::{Collector, Sample, Scale, Unit};
+
+#[derive(Debug, Clone, Copy, Default, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Mode {
+    /// Run the command once per sample and parse its whole output
+    #[default]
+    Interval,
+    /// Keep the command running and parse every line it prints
+    Stream,
+}
+
+/// Runs an external command and parses its output as a number, or as a JSON object if
+/// `field` is set.
+pub struct Command {
+    command: Vec<String>,
+    mode: Mode,
+    field: Option<String>,
+    unit: Unit,
+    scale: Scale,
+    /// How long a command may run in interval mode, or go without printing a value in stream
+    /// mode
+    timeout: Duration,
+    stream: Option<Stream>,
+}
+
+struct Stream {
+    child: Child,
+    /// Latest value and when it was printed
+    latest: Arc<Mutex<Option<(f64, Instant)>>>,
+}
+
+impl Drop for Stream {
+    fn drop(&mut self) {
+        _ = self.child.kill();
+        _ = self.child.wait();
+    }
+}
+
+impl Command {
+    pub fn new(
+        command: Vec<String>,
+        mode: Mode,
+        field: Option<String>,
+        unit: Unit,
+        scale: Scale,
+        timeout: Duration,
+    ) -> Result<Self> {
+        if command.is_empty() {
+            bail!("command must not be empty");
+        }
+        Ok(Self {
+            command,
+            mode,
+            field,
+            unit,
+            scale,
+            timeout,
+            stream: None,
+        })
+    }
+
+    fn command(&self) -> process::Command {
+        let mut command = process::Command::new(&self.command[0]);
+        command.args(&self.command[1..]).stdin(Stdio::null());
+        command
+    }
+
+    /// Runs the command to completion, killing it if it takes longer than the timeout.
+    fn run(&self) -> Result<f64> {
+        let deadline = Instant::now() + self.timeout;
+        let mut child = self
+            .command()
+            .stdout(Stdio::piped())
+            .stderr(Stdio::inherit())
+            .spawn()
+            .with_context(|| format!("running {:?}", self.command[0]))?;
+        // read on another thread so a command that never closes its output can be killed
+        let mut stdout = child.stdout.take().context("capturing stdout")?;
+        let (tx, rx) = mpsc::channel();
+        thread::spawn(move || {
+            let mut output = Vec::new();
+            _ = tx.send(stdout.read_to_end(&mut output).map(|_| output));
+        });
+        let output = match rx.recv_timeout(deadline.saturating_duration_since(Instant::now())) {
+            Ok(output) => output.context("reading output")?,
+            Err(_) => {
+                _ = child.kill();
+                _ = child.wait();
+                bail!("{:?} timed out after {:?}", self.command[0], self.timeout);
+            }
+        };
+        let status = self.wait(&mut child, deadline)?;
+        if !status.success() {
+            bail!("{:?} exited with {status}", self.command[0]);
+        }
+        parse(&String::from_utf8_lossy(&output), self.field.as_deref())
+    }
+
+    /// Waits for the command to exit, killing it at `deadline`.
+    fn wait(&self, child: &mut Child, deadline: Instant) -> Result<ExitStatus> {
+        loop {
+            if let Some(status) = child.try_wait().context("checking command")? {
+                return Ok(status);
+            }
+            if Instant::now() >= deadline {
+                _ = child.kill();
+                _ = child.wait();
+                bail!("{:?} timed out after {:?}", self.command[0], self.timeout);
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+    }
+
+    fn spawn(&self) -> Result<Stream> {
+        let mut child = self
+            .command()
+            .stdout(Stdio::piped())
+            .spawn()
+            .with_context(|| format!("spawning {:?}", self.command[0]))?;
+        let stdout = child.stdout.take().context("capturing stdout")?;
+        let latest = Arc::new(Mutex::new(None));
+        let latest_clone = Arc::clone(&latest);
+        let field = self.field.clone();
+        thread::spawn(move || {
+            for line in BufReader::new(stdout).lines() {
+                let Ok(line) = line else {
+                    break;
+                };
+                match parse(&line, field.as_deref()) {
+                    Ok(value) => *latest_clone.lock().unwrap() = Some((value, Instant::now())),
+                    Err(err) => debug!("ignoring line {line:?}: {err:#}"),
+                }
+            }
+        });
+        Ok(Stream { child, latest })
+    }
+}
+
+impl Collector for Command {
+    fn unit(&self) -> Unit {
+        self.unit
+    }
+
+    fn scale(&self) -> Scale {
+        self.scale
+    }
+
+    fn sample(&mut self) -> Result<Sample> {
+        match self.mode {
+            Mode::Interval => Ok(self.run()?.into()),
+            Mode::Stream => {
+                if let Some(stream) = &mut self.stream
+                    && let Some(status) = stream.child.try_wait().context("checking command")?
+                {
+                    self.stream = None;
+                    bail!("{:?} exited with {status}, restarting", self.command[0]);
+                }
+                let stream = match &mut self.stream {
+                    Some(stream) => stream,
+                    None => self.stream.insert(self.spawn()?),
+                };
+                let (value, time) = stream
+                    .latest
+                    .lock()
+                    .unwrap()
+                    .context("no value received yet")?;
+                if time.elapsed() > self.timeout {
+                    bail!(
+                        "{:?} printed no value for {:?}",
+                        self.command[0],
+                        time.elapsed()
+                    );
+                }
+                Ok(value.into())
+            }
+        }
+    }
+}
+
+/// Parses a plain number, or the number at the dot separated path `field` of a JSON object.
+fn parse(output: &str, field: Option<&str>) -> Result<f64> {
+    let Some(field) = field else {
+        return output.trim().parse().context("parsing output as number");
+    };
+    let json: serde_json::Value = serde_json::from_str(output).context("parsing output as JSON")?;
+    field
+        .split('.')
+        .try_fold(&json, |value, key| value.get(key))
+        .and_then(|value| value.as_f64())
+        .with_context(|| format!("output has no number at {field:?}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn command(args: &[&str], mode: Mode, timeout: Duration) -> Command {
+        Command::new(
+            args.iter().map(|arg| arg.to_string()).collect(),
+            mode,
+            None,
+            Unit::None,
+            Scale::PERCENT,
+            timeout,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn parses_plain_number() {
+        assert_eq!(parse("42\n", None).unwrap(), 42.);
+        assert_eq!(parse("  -1.5 ", None).unwrap(), -1.5);
+        assert!(parse("forty-two", None).is_err());
+        assert!(parse("", None).is_err());
+    }
+
+    #[test]
+    fn parses_json_field() {
+        let output = r#"{"queue": {"length": 7, "name": "ci"}, "total": 1.5}"#;
+        assert_eq!(parse(output, Some("total")).unwrap(), 1.5);
+        assert_eq!(parse(output, Some("queue.length")).unwrap(), 7.);
+        assert!(parse(output, Some("queue.name")).is_err());
+        assert!(parse(output, Some("queue.missing")).is_err());
+        assert!(parse("7", Some("queue")).is_err());
+        assert!(parse("not json", Some("queue")).is_err());
+    }
+
+    #[test]
+    fn runs_command() {
+        let mut command = command(&["echo", "12"], Mode::Interval, Duration::from_secs(5));
+        assert_eq!(command.sample().unwrap().value, 12.);
+    }
+
+    #[test]
+    fn kills_hung_command() {
+        let mut command = command(&["sleep", "10"], Mode::Interval, Duration::from_millis(100));
+        let start = Instant::now();
+        assert!(command.sample().is_err());
+        assert!(start.elapsed() < Duration::from_secs(5));
+    }
+
+    #[test]
+    fn stream_goes_missing_when_silent() {
+        let mut command = command(
+            &["sh", "-c", "echo 3; sleep 10"],
+            Mode::Stream,
+            Duration::from_secs(1),
+        );
+        // wait for the value instead of sleeping a fixed time, which is flaky on a busy machine
+        let start = Instant::now();
+        let value = loop {
+            if let Ok(sample) = command.sample() {
+                break sample.value;
+            }
+            assert!(start.elapsed() < Duration::from_secs(5), "no value read");
+            thread::sleep(Duration::from_millis(10));
+        };
+        assert_eq!(value, 3.);
+        let read = Instant::now();
+        while command.sample().is_ok() {
+            assert!(
+                read.elapsed() < Duration::from_secs(5),
+                "value never expired"
+            );
+            thread::sleep(Duration::from_millis(10));
+        }
+    }
+}
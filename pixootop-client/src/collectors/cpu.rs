@@ -0,0 +1,122 @@
+use std::fs;
+
+use anyhow::Result;
+use sysinfo::{CpuRefreshKind, RefreshKind, System};
+
+use super::{Collector, Sample, Scale, Unit};
+
+#[derive(Debug, Clone, Copy, Default, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Aggregate {
+    /// Average usage of all cores
+    #[default]
+    Average,
+    /// Usage of the busiest core, which shows a single pegged core
+    Max,
+}
+
+/// CPU usage in percent. The usage of each core is sent along for heatmaps.
+pub struct Cpu {
+    sys: System,
+    aggregate: Aggregate,
+}
+
+impl Cpu {
+    pub fn new(aggregate: Aggregate) -> Self {
+        Self {
+            sys: System::new(),
+            aggregate,
+        }
+    }
+}
+
+impl Collector for Cpu {
+    fn sample(&mut self) -> Result<Sample> {
+        self.sys.refresh_specifics(
+            RefreshKind::nothing().with_cpu(CpuRefreshKind::nothing().with_cpu_usage()),
+        );
+        let cores = self
+            .sys
+            .cpus()
+            .iter()
+            .map(|cpu| cpu.cpu_usage() as f64)
+            .collect::<Vec<_>>();
+        let value = match self.aggregate {
+            Aggregate::Average => cores.iter().sum::<f64>() / cores.len() as f64,
+            Aggregate::Max => cores.iter().copied().fold(0., f64::max),
+        };
+        Ok(Sample {
+            value,
+            parts: Some(cores.iter().map(|usage| usage / 100.).collect()),
+            ..Sample::default()
+        })
+    }
+}
+
+const CPUFREQ_PATH: &str = "/sys/devices/system/cpu";
+/// Used for a full bar if the maximum frequency can't be read
+const DEFAULT_MAX_FREQUENCY: f64 = 5_000_000_000.;
+
+/// CPU frequency in hertz, relative to the highest maximum frequency of all cores. The frequency
+/// of each core is sent along for heatmaps.
+pub struct Frequency {
+    sys: System,
+    aggregate: Aggregate,
+    max: f64,
+}
+
+impl Frequency {
+    pub fn new(aggregate: Aggregate) -> Self {
+        let sys =
+            System::new_with_specifics(RefreshKind::nothing().with_cpu(CpuRefreshKind::nothing()));
+        // in kHz
+        let max = (0..sys.cpus().len())
+            .filter_map(|core| {
+                fs::read_to_string(format!("{CPUFREQ_PATH}/cpu{core}/cpufreq/cpuinfo_max_freq"))
+                    .ok()?
+                    .trim()
+                    .parse::<f64>()
+                    .ok()
+            })
+            .map(|khz| khz * 1000.)
+            .reduce(f64::max)
+            .unwrap_or(DEFAULT_MAX_FREQUENCY);
+        Self {
+            sys,
+            aggregate,
+            max,
+        }
+    }
+}
+
+impl Collector for Frequency {
+    fn unit(&self) -> Unit {
+        Unit::Hertz
+    }
+
+    fn scale(&self) -> Scale {
+        Scale::max(self.max)
+    }
+
+    fn sample(&mut self) -> Result<Sample> {
+        self.sys.refresh_specifics(
+            RefreshKind::nothing().with_cpu(CpuRefreshKind::nothing().with_frequency()),
+        );
+        // in MHz
+        let cores = self
+            .sys
+            .cpus()
+            .iter()
+            .map(|cpu| cpu.frequency() as f64 * 1_000_000.)
+            .collect::<Vec<_>>();
+        let value = match self.aggregate {
+            Aggregate::Average => cores.iter().sum::<f64>() / cores.len() as f64,
+            Aggregate::Max => cores.iter().copied().fold(0., f64::max),
+        };
+        Ok(Sample {
+            value,
+            parts: Some(cores.iter().map(|hz| hz / self.max).collect()),
+            ..Sample::default()
+        })
+    }
+}
@@ -0,0 +1,90 @@
+use std::{fs, time::Instant};
+
+use anyhow::{Context as _, Result};
+
+use super::{Collector, Sample, Scale, Unavailable, Unit};
+use crate::average::Averaged;
+
+const DISKSTATS_PATH: &str = "/proc/diskstats";
+/// `/proc/diskstats` counts in sectors of 512 bytes, regardless of the device's sector size
+const SECTOR_SIZE: u64 = 512;
+
+#[derive(Debug, Clone, Copy, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Direction {
+    Read,
+    Write,
+}
+
+/// Bytes per second read from or written to a block device like `nvme0n1` or `sda1`.
+pub struct Disk {
+    device: String,
+    direction: Direction,
+    max: f64,
+    /// Sectors at the previous sample, and when they were read
+    last: Option<(u64, Instant)>,
+    data: Averaged<f64, 10>,
+}
+
+impl Disk {
+    pub fn new(device: String, direction: Direction, max: f64) -> Result<Self> {
+        let mut disk = Self {
+            device,
+            direction,
+            max,
+            last: None,
+            data: Averaged::new(0.),
+        };
+        if disk.sectors()?.is_none() {
+            Err(Unavailable("no such block device"))?;
+        }
+        Ok(disk)
+    }
+
+    /// Total sectors read or written since boot, or `None` if the device doesn't exist.
+    fn sectors(&mut self) -> Result<Option<u64>> {
+        let stats = fs::read_to_string(DISKSTATS_PATH)
+            .with_context(|| format!("reading {DISKSTATS_PATH}"))?;
+        // major, minor, name, then reads completed, reads merged, sectors read, time reading,
+        // writes completed, writes merged, sectors written, ...
+        let field = match self.direction {
+            Direction::Read => 5,
+            Direction::Write => 9,
+        };
+        stats
+            .lines()
+            .map(|line| line.split_whitespace().collect::<Vec<_>>())
+            .find(|fields| fields.get(2) == Some(&self.device.as_str()))
+            .map(|fields| {
+                fields
+                    .get(field)
+                    .context("diskstats line is too short")?
+                    .parse()
+                    .context("parsing diskstats")
+            })
+            .transpose()
+    }
+}
+
+impl Collector for Disk {
+    fn unit(&self) -> Unit {
+        Unit::BytesPerSecond
+    }
+
+    fn scale(&self) -> Scale {
+        Scale::max(self.max)
+    }
+
+    fn sample(&mut self) -> Result<Sample> {
+        let sectors = self.sectors()?.context("block device disappeared")?;
+        let now = Instant::now();
+        let rate = match self.last.replace((sectors, now)) {
+            Some((last, time)) => {
+                (sectors.saturating_sub(last) * SECTOR_SIZE) as f64
+                    / now.duration_since(time).as_secs_f64()
+            }
+            None => 0.,
+        };
+        Ok(self.data.next(rate).into())
+    }
+}
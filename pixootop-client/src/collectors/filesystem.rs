@@ -0,0 +1,38 @@
+use std::path::PathBuf;
+
+use anyhow::{Context as _, Result, bail};
+use sysinfo::Disks;
+
+use super::{Collector, Sample, Unavailable};
+
+/// How full the filesystem mounted at a path is.
+pub struct Filesystem {
+    disks: Disks,
+    mount_point: PathBuf,
+}
+
+impl Filesystem {
+    pub fn new(mount_point: PathBuf) -> Result<Self> {
+        let disks = Disks::new_with_refreshed_list();
+        if !disks.iter().any(|disk| disk.mount_point() == mount_point) {
+            Err(Unavailable("nothing is mounted there"))?;
+        }
+        Ok(Self { disks, mount_point })
+    }
+}
+
+impl Collector for Filesystem {
+    fn sample(&mut self) -> Result<Sample> {
+        self.disks.refresh(true);
+        let disk = self
+            .disks
+            .iter()
+            .find(|disk| disk.mount_point() == self.mount_point)
+            .with_context(|| format!("{} is not mounted", self.mount_point.display()))?;
+        let total = disk.total_space() as f64;
+        if total == 0. {
+            bail!("{} reports no size", self.mount_point.display());
+        }
+        Ok(((total - disk.available_space() as f64) / total * 100.).into())
+    }
+}
@@ -0,0 +1,47 @@
+use std::path::Path;
+
+use amdgpu_sysfs::gpu_handle::GpuHandle;
+use anyhow::{Context as _, Result};
+use log::debug;
+
+use super::GpuBackend;
+
+pub struct AmdGpu {
+    gpu: GpuHandle,
+}
+
+impl AmdGpu {
+    pub fn new(card: &Path) -> Result<Self> {
+        Ok(Self {
+            gpu: GpuHandle::new_from_path(card.join("device")).context("getting gpu handle")?,
+        })
+    }
+}
+
+impl GpuBackend for AmdGpu {
+    fn busy_percent(&mut self) -> Result<f64> {
+        Ok(self.gpu.get_busy_percent().context("reading GPU usage")? as f64)
+    }
+
+    fn vram(&mut self) -> Result<(u64, u64)> {
+        let used = self.gpu.get_used_vram().context("reading GPU memory")?;
+        let total = self
+            .gpu
+            .get_total_vram()
+            .context("reading GPU total memory")?;
+        Ok((used, total))
+    }
+
+    fn power(&mut self) -> Result<(f64, f64)> {
+        let hwmon = self.gpu.hw_monitors.first().context("GPU has no hwmon")?;
+        let power = hwmon
+            .get_power_average()
+            .or_else(|err| {
+                debug!("no average power, using current: {err}");
+                hwmon.get_power_input()
+            })
+            .context("reading GPU power")?;
+        let cap = hwmon.get_power_cap().context("reading GPU power cap")?;
+        Ok((power, cap))
+    }
+}
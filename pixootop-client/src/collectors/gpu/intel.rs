@@ -0,0 +1,172 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    time::Instant,
+};
+
+use anyhow::{Context as _, Result, bail};
+
+use super::GpuBackend;
+
+/// Derives the usage from how long the render engine was idle since the last sample, which
+/// both i915 (RC6 residency) and xe (GT idle residency) expose in sysfs.
+pub struct Intel {
+    idle_residency: PathBuf,
+    last: (u64, Instant),
+    /// Only for discrete GPUs like Arc
+    local_memory: Option<LocalMemory>,
+}
+
+/// Files with the size of a discrete GPU's local memory and how much of it is used or free
+struct LocalMemory {
+    total: PathBuf,
+    usage: PathBuf,
+    /// Whether `usage` holds the free bytes instead of the used bytes
+    free: bool,
+}
+
+impl LocalMemory {
+    fn of(card: &Path) -> Option<Self> {
+        [
+            // i915
+            ("lmem_total_bytes", "lmem_avail_bytes", true),
+            (
+                "device/mem_info_vram_total",
+                "device/mem_info_vram_used",
+                false,
+            ),
+        ]
+        .into_iter()
+        .map(|(total, usage, free)| Self {
+            total: card.join(total),
+            usage: card.join(usage),
+            free,
+        })
+        .find(|memory| memory.total.exists() && memory.usage.exists())
+    }
+}
+
+/// Whether the card is a discrete GPU, which unlike integrated GPUs has local memory.
+pub fn is_discrete(card: &Path) -> bool {
+    LocalMemory::of(card).is_some()
+}
+
+impl Intel {
+    pub fn new(card: &Path) -> Result<Self> {
+        let idle_residency = [
+            // xe
+            "device/tile0/gt0/gtidle/idle_residency_ms",
+            // i915
+            "gt/gt0/rc6_residency_ms",
+            // i915 before Linux 5.17
+            "power/rc6_residency_ms",
+        ]
+        .into_iter()
+        .map(|path| card.join(path))
+        .find(|path| path.exists())
+        .context("GPU exposes no idle residency")?;
+        Ok(Self {
+            last: (read(&idle_residency)?, Instant::now()),
+            idle_residency,
+            local_memory: LocalMemory::of(card),
+        })
+    }
+}
+
+impl GpuBackend for Intel {
+    fn busy_percent(&mut self) -> Result<f64> {
+        let idle = read(&self.idle_residency)?;
+        let now = Instant::now();
+        let (last_idle, last_time) = self.last;
+        self.last = (idle, now);
+
+        let elapsed = now.duration_since(last_time).as_secs_f64() * 1000.;
+        if elapsed <= 0. {
+            bail!("no time passed since last sample");
+        }
+        let idle = idle.saturating_sub(last_idle) as f64;
+        Ok(((1. - idle / elapsed) * 100.).clamp(0., 100.))
+    }
+
+    fn has_vram(&self) -> bool {
+        self.local_memory.is_some()
+    }
+
+    fn vram(&mut self) -> Result<(u64, u64)> {
+        let Some(memory) = &self.local_memory else {
+            bail!("integrated GPUs have no dedicated memory");
+        };
+        let total = read(&memory.total)?;
+        let usage = read(&memory.usage)?;
+        let used = match memory.free {
+            true => total.saturating_sub(usage),
+            false => usage,
+        };
+        Ok((used, total))
+    }
+}
+
+fn read(path: &Path) -> Result<u64> {
+    fs::read_to_string(path)
+        .with_context(|| format!("reading {}", path.display()))?
+        .trim()
+        .parse()
+        .with_context(|| format!("parsing {}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    #[test]
+    fn reads_busy_percent_from_idle_residency() {
+        let card = tempfile::tempdir().unwrap();
+        fs::create_dir_all(card.path().join("gt/gt0")).unwrap();
+        let residency = card.path().join("gt/gt0/rc6_residency_ms");
+        fs::write(&residency, "1000\n").unwrap();
+
+        let mut intel = Intel::new(card.path()).unwrap();
+        assert!(!intel.has_vram());
+        intel.last = (1000, Instant::now() - Duration::from_secs(1));
+        fs::write(&residency, "1250\n").unwrap();
+        let busy = intel.busy_percent().unwrap();
+        assert!((74. ..=76.).contains(&busy), "{busy}");
+    }
+
+    #[test]
+    fn prefers_xe_idle_residency() {
+        let card = tempfile::tempdir().unwrap();
+        let gtidle = card.path().join("device/tile0/gt0/gtidle");
+        fs::create_dir_all(&gtidle).unwrap();
+        fs::write(gtidle.join("idle_residency_ms"), "5").unwrap();
+        fs::create_dir_all(card.path().join("gt/gt0")).unwrap();
+        fs::write(card.path().join("gt/gt0/rc6_residency_ms"), "7").unwrap();
+
+        let intel = Intel::new(card.path()).unwrap();
+        assert_eq!(intel.idle_residency, gtidle.join("idle_residency_ms"));
+        assert_eq!(intel.last.0, 5);
+    }
+
+    #[test]
+    fn fails_without_idle_residency() {
+        let card = tempfile::tempdir().unwrap();
+        assert!(Intel::new(card.path()).is_err());
+    }
+
+    #[test]
+    fn reads_local_memory_of_discrete_gpus() {
+        let card = tempfile::tempdir().unwrap();
+        fs::create_dir_all(card.path().join("gt/gt0")).unwrap();
+        fs::write(card.path().join("gt/gt0/rc6_residency_ms"), "0").unwrap();
+        assert!(!is_discrete(card.path()));
+
+        fs::write(card.path().join("lmem_total_bytes"), "16000\n").unwrap();
+        fs::write(card.path().join("lmem_avail_bytes"), "12000\n").unwrap();
+        assert!(is_discrete(card.path()));
+        let mut intel = Intel::new(card.path()).unwrap();
+        assert!(intel.has_vram());
+        assert_eq!(intel.vram().unwrap(), (4000, 16000));
+    }
+}
@@ -0,0 +1,388 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context as _, Result, bail};
+use log::info;
+
+use super::{Collector, Sample, Scale, Unavailable, Unit};
+use crate::average::Averaged;
+
+mod amdgpu;
+mod intel;
+mod nvidia;
+
+pub const DRM_PATH: &str = "/sys/class/drm";
+
+/// A way to read GPU usage for one vendor's driver.
+pub trait GpuBackend {
+    fn busy_percent(&mut self) -> Result<f64>;
+
+    /// Whether the GPU has dedicated video memory, which integrated GPUs don't
+    fn has_vram(&self) -> bool {
+        true
+    }
+
+    /// Used and total video memory in bytes
+    fn vram(&mut self) -> Result<(u64, u64)>;
+
+    /// Current power draw and power limit in watts
+    fn power(&mut self) -> Result<(f64, f64)> {
+        bail!("reading the power draw is not supported for this GPU")
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Driver {
+    AmdGpu,
+    Nvidia,
+    Intel,
+}
+
+impl Driver {
+    /// Reads the kernel driver bound to a DRM card like `/sys/class/drm/card1`.
+    fn of(card: &Path) -> Option<Self> {
+        let driver = fs::read_link(card.join("device/driver")).ok()?;
+        match driver.file_name()?.to_str()? {
+            "amdgpu" => Some(Self::AmdGpu),
+            "nvidia" => Some(Self::Nvidia),
+            "i915" | "xe" => Some(Self::Intel),
+            _ => None,
+        }
+    }
+
+    fn backend(self, card: &Path) -> Result<Box<dyn GpuBackend>> {
+        Ok(match self {
+            Self::AmdGpu => Box::new(amdgpu::AmdGpu::new(card)?),
+            Self::Nvidia => Box::new(nvidia::Nvidia::new(card)?),
+            Self::Intel => Box::new(intel::Intel::new(card)?),
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Aggregate {
+    /// Report only the first matching card
+    #[default]
+    Single,
+    /// Report the highest value of all matching cards
+    Max,
+    /// Report a separate metric for each matching card
+    Each,
+}
+
+/// Lists the DRM cards below `drm`, skipping connectors like `card1-DP-1`.
+fn cards(drm: &Path) -> Result<Vec<PathBuf>> {
+    let mut cards = fs::read_dir(drm)
+        .with_context(|| format!("listing {}", drm.display()))?
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| {
+            entry
+                .file_name()
+                .to_str()
+                .and_then(|name| name.strip_prefix("card"))
+                .is_some_and(|num| num.parse::<u32>().is_ok())
+        })
+        .map(|entry| entry.path())
+        .collect::<Vec<_>>();
+    cards.sort();
+    Ok(cards)
+}
+
+/// Whether a card matches a selector, which is either the card's path, its name like `card1`,
+/// its PCI address like `0000:03:00.0`, or its PCI vendor and device ID like `1002:73bf`.
+fn matches(card: &Path, selector: &str) -> bool {
+    if selector.starts_with('/') {
+        return card == Path::new(selector);
+    }
+    if card.file_name().is_some_and(|name| name == selector) {
+        return true;
+    }
+    let device = card.join("device");
+    if fs::canonicalize(&device)
+        .ok()
+        .is_some_and(|path| path.file_name().is_some_and(|name| name == selector))
+    {
+        return true;
+    }
+    let read_id = |file| {
+        fs::read_to_string(device.join(file))
+            .ok()
+            .map(|id| id.trim().trim_start_matches("0x").to_lowercase())
+    };
+    match (read_id("vendor"), read_id("device")) {
+        (Some(vendor), Some(device)) => format!("{vendor}:{device}") == selector.to_lowercase(),
+        _ => false,
+    }
+}
+
+/// Finds all cards with a supported driver matching the selector, dedicated GPUs first.
+pub fn select(drm: &Path, selector: Option<&str>) -> Result<Vec<PathBuf>> {
+    let mut found = cards(drm)?
+        .into_iter()
+        .filter(|card| selector.is_none_or(|selector| matches(card, selector)))
+        .filter_map(|card| Some((Driver::of(&card)?, card)))
+        .collect::<Vec<_>>();
+    found.sort_by_key(|(driver, card)| *driver == Driver::Intel && !intel::is_discrete(card));
+    if found.is_empty() {
+        Err(Unavailable("no matching GPU found"))?;
+    }
+    Ok(found.into_iter().map(|(_, card)| card).collect())
+}
+
+/// Opens the first or all selected cards.
+fn open(
+    drm: &Path,
+    selector: Option<&str>,
+    aggregate: Aggregate,
+) -> Result<Vec<Box<dyn GpuBackend>>> {
+    let mut cards = select(drm, selector)?;
+    if let Aggregate::Single | Aggregate::Each = aggregate {
+        cards.truncate(1);
+    }
+    cards
+        .iter()
+        .map(|card| {
+            let driver = Driver::of(card).context("reading GPU driver")?;
+            info!("using {driver:?} GPU at {}", card.display());
+            driver.backend(card)
+        })
+        .collect()
+}
+
+/// Highest value of all cards, ignoring cards which fail as long as one doesn't.
+fn max(
+    backends: &mut [Box<dyn GpuBackend>],
+    mut read: impl FnMut(&mut dyn GpuBackend) -> Result<f64>,
+) -> Result<f64> {
+    let mut result = Err(anyhow::anyhow!("no GPU to read"));
+    for backend in backends {
+        result = match (result, read(backend.as_mut())) {
+            (Ok(max), Ok(value)) => Ok(value.max(max)),
+            (Ok(max), Err(_)) | (Err(_), Ok(max)) => Ok(max),
+            (Err(_), Err(err)) => Err(err),
+        };
+    }
+    result
+}
+
+pub struct Gpu {
+    backends: Vec<Box<dyn GpuBackend>>,
+    data: Averaged<f64, 25>,
+}
+
+impl Gpu {
+    pub fn new(drm: &Path, selector: Option<&str>, aggregate: Aggregate) -> Result<Self> {
+        Ok(Self {
+            backends: open(drm, selector, aggregate)?,
+            data: Averaged::new(0.),
+        })
+    }
+}
+
+impl Collector for Gpu {
+    fn sample(&mut self) -> Result<Sample> {
+        let busy = max(&mut self.backends, |backend| backend.busy_percent())?;
+        Ok(self.data.next(busy).into())
+    }
+}
+
+pub struct Vram {
+    backends: Vec<Box<dyn GpuBackend>>,
+}
+
+impl Vram {
+    pub fn new(drm: &Path, selector: Option<&str>, aggregate: Aggregate) -> Result<Self> {
+        let mut backends = open(drm, selector, aggregate)?;
+        backends.retain(|backend| backend.has_vram());
+        if backends.is_empty() {
+            Err(Unavailable("GPU has no dedicated memory"))?;
+        }
+        Ok(Self { backends })
+    }
+}
+
+impl Collector for Vram {
+    fn sample(&mut self) -> Result<Sample> {
+        Ok(max(&mut self.backends, |backend| {
+            let (used, total) = backend.vram()?;
+            Ok(used as f64 / total as f64 * 100.)
+        })?
+        .into())
+    }
+}
+
+pub struct GpuPower {
+    backends: Vec<Box<dyn GpuBackend>>,
+    scale: Scale,
+}
+
+impl GpuPower {
+    /// Without `max`, the highest power limit of the GPUs is used for a full bar.
+    pub fn new(
+        drm: &Path,
+        selector: Option<&str>,
+        aggregate: Aggregate,
+        max: Option<f64>,
+    ) -> Result<Self> {
+        let mut backends = open(drm, selector, aggregate)?;
+        let mut limit = 0f64;
+        backends.retain_mut(|backend| match backend.power() {
+            Ok((_, cap)) => {
+                limit = limit.max(cap);
+                true
+            }
+            Err(_) => false,
+        });
+        if backends.is_empty() {
+            Err(Unavailable("GPU has no power sensor"))?;
+        }
+        Ok(Self {
+            backends,
+            scale: Scale::max(max.unwrap_or(limit)),
+        })
+    }
+}
+
+impl Collector for GpuPower {
+    fn unit(&self) -> Unit {
+        Unit::Watts
+    }
+
+    fn scale(&self) -> Scale {
+        self.scale
+    }
+
+    fn sample(&mut self) -> Result<Sample> {
+        Ok(max(&mut self.backends, |backend| Ok(backend.power()?.0))?.into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::os::unix::fs::symlink;
+
+    use tempfile::TempDir;
+
+    use super::*;
+
+    /// Creates a DRM class directory with a card per `(name, PCI address, driver)`, and a
+    /// connector like a real one.
+    fn fake_drm(cards: &[(&str, &str, &str)]) -> TempDir {
+        let root = tempfile::tempdir().unwrap();
+        let drm = root.path().join("class/drm");
+        fs::create_dir_all(&drm).unwrap();
+        for (name, address, driver) in cards {
+            let device = root.path().join("devices").join(address);
+            fs::create_dir_all(&device).unwrap();
+            symlink(format!("../../drivers/{driver}"), device.join("driver")).unwrap();
+            fs::create_dir(drm.join(name)).unwrap();
+            symlink(&device, drm.join(name).join("device")).unwrap();
+        }
+        fs::create_dir(drm.join("card1-DP-1")).unwrap();
+        fs::write(drm.join("version"), "drm 1.1.0").unwrap();
+        root
+    }
+
+    fn drm(root: &TempDir) -> PathBuf {
+        root.path().join("class/drm")
+    }
+
+    #[test]
+    fn lists_cards_without_connectors() {
+        let root = fake_drm(&[
+            ("card1", "0000:03:00.0", "amdgpu"),
+            ("card0", "0000:00:02.0", "i915"),
+        ]);
+        let drm = drm(&root);
+        assert_eq!(cards(&drm).unwrap(), [drm.join("card0"), drm.join("card1")]);
+    }
+
+    #[test]
+    fn detects_drivers() {
+        let root = fake_drm(&[
+            ("card0", "0000:00:02.0", "i915"),
+            ("card1", "0000:03:00.0", "amdgpu"),
+            ("card2", "0000:04:00.0", "nvidia"),
+            ("card3", "0000:05:00.0", "xe"),
+            ("card4", "0000:06:00.0", "nouveau"),
+        ]);
+        let drm = drm(&root);
+        let drivers = cards(&drm)
+            .unwrap()
+            .iter()
+            .map(|card| Driver::of(card))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            drivers,
+            [
+                Some(Driver::Intel),
+                Some(Driver::AmdGpu),
+                Some(Driver::Nvidia),
+                Some(Driver::Intel),
+                None,
+            ]
+        );
+    }
+
+    #[test]
+    fn selects_dedicated_gpus_first() {
+        let root = fake_drm(&[
+            ("card0", "0000:00:02.0", "i915"),
+            ("card1", "0000:03:00.0", "amdgpu"),
+            ("card2", "0000:04:00.0", "nouveau"),
+        ]);
+        let drm = drm(&root);
+        assert_eq!(
+            select(&drm, None).unwrap(),
+            [drm.join("card1"), drm.join("card0")]
+        );
+        assert_eq!(select(&drm, Some("card0")).unwrap(), [drm.join("card0")]);
+        assert!(select(&drm, Some("card2")).unwrap_err().is::<Unavailable>());
+        assert!(select(&drm, Some("card9")).unwrap_err().is::<Unavailable>());
+    }
+
+    #[test]
+    fn matches_cards_by_path_name_address_and_id() {
+        let root = fake_drm(&[
+            ("card0", "0000:00:02.0", "i915"),
+            ("card1", "0000:03:00.0", "amdgpu"),
+        ]);
+        let drm = drm(&root);
+        let card = drm.join("card1");
+        fs::write(card.join("device/vendor"), "0x1002\n").unwrap();
+        fs::write(card.join("device/device"), "0x73BF\n").unwrap();
+
+        assert!(matches(&card, card.to_str().unwrap()));
+        assert!(matches(&card, "card1"));
+        assert!(matches(&card, "0000:03:00.0"));
+        assert!(matches(&card, "1002:73bf"));
+        assert!(matches(&card, "1002:73BF"));
+
+        assert!(!matches(&card, drm.join("card0").to_str().unwrap()));
+        assert!(!matches(&card, "card0"));
+        assert!(!matches(&card, "card"));
+        assert!(!matches(&card, "0000:00:02.0"));
+        assert!(!matches(&card, "1002:73be"));
+        assert!(!matches(&card, "10de:73bf"));
+        // the card without IDs only matches by name, path and address
+        assert!(!matches(&drm.join("card0"), "8086:56a0"));
+    }
+
+    #[test]
+    fn treats_intel_gpus_with_local_memory_as_dedicated() {
+        let root = fake_drm(&[
+            ("card0", "0000:00:02.0", "i915"),
+            ("card1", "0000:03:00.0", "i915"),
+        ]);
+        let drm = drm(&root);
+        fs::write(drm.join("card1/lmem_total_bytes"), "8000").unwrap();
+        fs::write(drm.join("card1/lmem_avail_bytes"), "6000").unwrap();
+        assert_eq!(
+            select(&drm, None).unwrap(),
+            [drm.join("card1"), drm.join("card0")]
+        );
+    }
+}
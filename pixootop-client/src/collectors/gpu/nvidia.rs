@@ -0,0 +1,56 @@
+use std::{ffi::OsStr, path::Path};
+
+use anyhow::{Context as _, Result};
+use nvml_wrapper::Nvml;
+
+use super::GpuBackend;
+
+/// Uses NVML, which is loaded at runtime so the client also runs on machines without the
+/// NVIDIA driver.
+pub struct Nvidia {
+    nvml: Nvml,
+    pci_bus_id: String,
+}
+
+impl Nvidia {
+    pub fn new(card: &Path) -> Result<Self> {
+        let pci_bus_id = card
+            .join("device")
+            .canonicalize()
+            .context("resolving PCI device")?
+            .file_name()
+            .context("resolving PCI device")?
+            .to_string_lossy()
+            .into_owned();
+        let nvml = Nvml::builder()
+            .lib_path(OsStr::new("libnvidia-ml.so.1"))
+            .init()
+            .context("loading NVML")?;
+        nvml.device_by_pci_bus_id(pci_bus_id.as_str())
+            .with_context(|| format!("getting NVML device {pci_bus_id}"))?;
+        Ok(Self { nvml, pci_bus_id })
+    }
+}
+
+impl GpuBackend for Nvidia {
+    fn busy_percent(&mut self) -> Result<f64> {
+        let device = self.nvml.device_by_pci_bus_id(self.pci_bus_id.as_str())?;
+        Ok(device.utilization_rates().context("reading GPU usage")?.gpu as f64)
+    }
+
+    fn vram(&mut self) -> Result<(u64, u64)> {
+        let device = self.nvml.device_by_pci_bus_id(self.pci_bus_id.as_str())?;
+        let info = device.memory_info().context("reading GPU memory")?;
+        Ok((info.used, info.total))
+    }
+
+    fn power(&mut self) -> Result<(f64, f64)> {
+        let device = self.nvml.device_by_pci_bus_id(self.pci_bus_id.as_str())?;
+        // NVML uses milliwatts
+        let power = device.power_usage().context("reading GPU power")?;
+        let limit = device
+            .power_management_limit()
+            .context("reading GPU power limit")?;
+        Ok((power as f64 / 1000., limit as f64 / 1000.))
+    }
+}
@@ -0,0 +1,244 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context as _, Result};
+
+use super::{Collector, Sample, Scale, Unavailable, Unit};
+
+pub const HWMON_PATH: &str = "/sys/class/hwmon";
+
+#[derive(Debug, Clone, Copy, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SensorKind {
+    Temp,
+    Fan,
+    Power,
+}
+
+impl SensorKind {
+    /// Prefix of the sysfs files for this kind of sensor
+    pub fn prefix(self) -> &'static str {
+        match self {
+            Self::Temp => "temp",
+            Self::Fan => "fan",
+            Self::Power => "power",
+        }
+    }
+
+    pub fn default_scale(self) -> Scale {
+        match self {
+            Self::Temp => Scale {
+                min: 30.,
+                max: 100.,
+            },
+            Self::Fan => Scale::max(3000.),
+            Self::Power => Scale::max(300.),
+        }
+    }
+}
+
+/// Reads one sensor of a hardware monitoring chip, like the CPU package temperature of
+/// `k10temp` or `coretemp`, or the junction temperature of `amdgpu`.
+pub struct Hwmon {
+    kind: SensorKind,
+    input: PathBuf,
+    scale: Scale,
+}
+
+impl Hwmon {
+    /// Finds the first sensor of the given kind whose chip is called `chip` and whose label or
+    /// channel name like `temp2` is `sensor`. Without `sensor`, the first channel is used.
+    pub fn new(
+        root: &Path,
+        chip: &str,
+        kind: SensorKind,
+        sensor: Option<&str>,
+        scale: Scale,
+    ) -> Result<Self> {
+        let mut chips = fs::read_dir(root)
+            .with_context(|| format!("listing {}", root.display()))?
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+            .filter(|path| {
+                fs::read_to_string(path.join("name")).is_ok_and(|name| name.trim() == chip)
+            })
+            .collect::<Vec<_>>();
+        chips.sort();
+
+        let input = chips
+            .iter()
+            .find_map(|dir| find_channel(dir, kind, sensor))
+            .ok_or(Unavailable("no matching hwmon sensor found"))?;
+        Ok(Self { kind, input, scale })
+    }
+}
+
+fn find_channel(dir: &Path, kind: SensorKind, sensor: Option<&str>) -> Option<PathBuf> {
+    let prefix = kind.prefix();
+    let mut channels = fs::read_dir(dir)
+        .ok()?
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let file = entry.file_name();
+            let file = file.to_str()?;
+            // some power sensors only have an average
+            let channel = file
+                .strip_suffix("_input")
+                .or_else(|| file.strip_suffix("_average"))?;
+            channel
+                .strip_prefix(prefix)?
+                .parse::<u32>()
+                .ok()
+                .map(|num| (num, channel.to_string()))
+        })
+        .collect::<Vec<_>>();
+    channels.sort();
+    channels.dedup();
+
+    let (_, channel) = channels.into_iter().find(|(_, channel)| {
+        sensor.is_none_or(|sensor| {
+            channel == sensor
+                || fs::read_to_string(dir.join(format!("{channel}_label")))
+                    .is_ok_and(|label| label.trim() == sensor)
+        })
+    })?;
+    // prefer the average power, which is less noisy
+    let average = dir.join(format!("{channel}_average"));
+    match average.exists() {
+        true => Some(average),
+        false => Some(dir.join(format!("{channel}_input"))),
+    }
+}
+
+impl Collector for Hwmon {
+    fn unit(&self) -> Unit {
+        match self.kind {
+            SensorKind::Temp => Unit::Celsius,
+            SensorKind::Fan => Unit::Rpm,
+            SensorKind::Power => Unit::Watts,
+        }
+    }
+
+    fn scale(&self) -> Scale {
+        self.scale
+    }
+
+    fn sample(&mut self) -> Result<Sample> {
+        let raw = fs::read_to_string(&self.input)
+            .with_context(|| format!("reading {}", self.input.display()))?
+            .trim()
+            .parse::<f64>()
+            .with_context(|| format!("parsing {}", self.input.display()))?;
+        // hwmon uses millidegrees Celsius and microwatts
+        Ok(match self.kind {
+            SensorKind::Temp => raw / 1000.,
+            SensorKind::Fan => raw,
+            SensorKind::Power => raw / 1_000_000.,
+        }
+        .into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chip(root: &Path, dir: &str, files: &[(&str, &str)]) -> PathBuf {
+        let dir = root.join(dir);
+        fs::create_dir(&dir).unwrap();
+        for (file, content) in files {
+            fs::write(dir.join(file), format!("{content}\n")).unwrap();
+        }
+        dir
+    }
+
+    #[test]
+    fn finds_channels_by_label_or_name() {
+        let root = tempfile::tempdir().unwrap();
+        let dir = chip(
+            root.path(),
+            "hwmon0",
+            &[
+                ("name", "k10temp"),
+                ("temp3_input", "41000"),
+                ("temp3_label", "Tccd1"),
+                ("temp1_input", "52125"),
+                ("temp1_label", "Tctl"),
+                ("fan1_input", "1200"),
+            ],
+        );
+
+        let find = |kind, sensor| find_channel(&dir, kind, sensor);
+        assert_eq!(find(SensorKind::Temp, None), Some(dir.join("temp1_input")));
+        assert_eq!(
+            find(SensorKind::Temp, Some("Tccd1")),
+            Some(dir.join("temp3_input"))
+        );
+        assert_eq!(
+            find(SensorKind::Temp, Some("temp3")),
+            Some(dir.join("temp3_input"))
+        );
+        assert_eq!(find(SensorKind::Temp, Some("Tccd2")), None);
+        assert_eq!(find(SensorKind::Fan, None), Some(dir.join("fan1_input")));
+        assert_eq!(find(SensorKind::Power, None), None);
+    }
+
+    #[test]
+    fn prefers_average_power() {
+        let root = tempfile::tempdir().unwrap();
+        let dir = chip(
+            root.path(),
+            "hwmon0",
+            &[
+                ("name", "amdgpu"),
+                ("power1_input", "90000000"),
+                ("power1_average", "85000000"),
+                ("power2_average", "5000000"),
+            ],
+        );
+        assert_eq!(
+            find_channel(&dir, SensorKind::Power, None),
+            Some(dir.join("power1_average"))
+        );
+        assert_eq!(
+            find_channel(&dir, SensorKind::Power, Some("power2")),
+            Some(dir.join("power2_average"))
+        );
+    }
+
+    #[test]
+    fn reads_sensor_of_named_chip() {
+        let root = tempfile::tempdir().unwrap();
+        chip(
+            root.path(),
+            "hwmon0",
+            &[("name", "nvme"), ("temp1_input", "38850")],
+        );
+        chip(
+            root.path(),
+            "hwmon1",
+            &[
+                ("name", "amdgpu"),
+                ("temp2_input", "64000"),
+                ("temp2_label", "junction"),
+                ("power1_average", "85000000"),
+            ],
+        );
+
+        let hwmon = |chip, kind, sensor| {
+            Hwmon::new(root.path(), chip, kind, sensor, kind.default_scale())?.sample()
+        };
+        assert_eq!(
+            hwmon("amdgpu", SensorKind::Temp, Some("junction"))
+                .unwrap()
+                .value,
+            64.
+        );
+        assert_eq!(hwmon("nvme", SensorKind::Temp, None).unwrap().value, 38.85);
+        assert_eq!(hwmon("amdgpu", SensorKind::Power, None).unwrap().value, 85.);
+        let err = hwmon("k10temp", SensorKind::Temp, None).unwrap_err();
+        assert!(err.is::<Unavailable>());
+    }
+}
@@ -0,0 +1,221 @@
+use std::{fs, path::Path, thread};
+
+use anyhow::{Context as _, Result};
+
+use super::{Collector, PROC_PATH, Sample, Scale, Unit};
+
+const LOADAVG_PATH: &str = "/proc/loadavg";
+
+#[derive(Debug, Clone, Copy, Default, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Period {
+    #[default]
+    #[serde(rename = "1m")]
+    OneMinute,
+    #[serde(rename = "5m")]
+    FiveMinutes,
+    #[serde(rename = "15m")]
+    FifteenMinutes,
+}
+
+/// Number of logical cores, which is a full load
+fn cores() -> f64 {
+    thread::available_parallelism().map_or(1, |cores| cores.get()) as f64
+}
+
+/// Contents of `/proc/loadavg`
+struct Loadavg {
+    averages: [f64; 3],
+    running: f64,
+    total: f64,
+}
+
+fn read() -> Result<Loadavg> {
+    parse(&fs::read_to_string(LOADAVG_PATH).with_context(|| format!("reading {LOADAVG_PATH}"))?)
+}
+
+/// Parses `/proc/loadavg`, like `0.52 0.58 0.59 2/1234 5678`.
+fn parse(loadavg: &str) -> Result<Loadavg> {
+    let mut fields = loadavg.split_whitespace();
+    let mut average = || -> Result<f64> {
+        fields
+            .next()
+            .context("unexpected loadavg format")?
+            .parse()
+            .context("parsing load average")
+    };
+    let averages = [average()?, average()?, average()?];
+    let (running, total) = fields
+        .next()
+        .and_then(|field| field.split_once('/'))
+        .context("unexpected loadavg format")?;
+    Ok(Loadavg {
+        averages,
+        running: running.parse().context("parsing thread count")?,
+        total: total.parse().context("parsing thread count")?,
+    })
+}
+
+/// Load average, where a full bar means as many runnable tasks as there are cores.
+pub struct Load {
+    period: Period,
+    cores: f64,
+}
+
+impl Load {
+    pub fn new(period: Period) -> Self {
+        Self {
+            period,
+            cores: cores(),
+        }
+    }
+}
+
+impl Collector for Load {
+    fn unit(&self) -> Unit {
+        Unit::None
+    }
+
+    fn scale(&self) -> Scale {
+        Scale::max(self.cores)
+    }
+
+    fn sample(&mut self) -> Result<Sample> {
+        let [one, five, fifteen] = read()?.averages;
+        Ok(match self.period {
+            Period::OneMinute => one,
+            Period::FiveMinutes => five,
+            Period::FifteenMinutes => fifteen,
+        }
+        .into())
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Count {
+    /// Running processes
+    #[default]
+    Running,
+    /// All processes
+    Total,
+    /// Runnable threads, as the kernel schedules threads rather than processes
+    RunningThreads,
+    /// All threads
+    Threads,
+}
+
+/// Number of running or existing processes from `/proc`, or of threads as counted in
+/// `/proc/loadavg`.
+pub struct Processes {
+    count: Count,
+    max: f64,
+}
+
+impl Processes {
+    /// Without `max`, the number of cores is used for running processes or threads, 500 for all
+    /// processes and 1000 for all threads.
+    pub fn new(count: Count, max: Option<f64>) -> Self {
+        Self {
+            count,
+            max: max.unwrap_or_else(|| match count {
+                Count::Running | Count::RunningThreads => cores(),
+                Count::Total => 500.,
+                Count::Threads => 1000.,
+            }),
+        }
+    }
+}
+
+impl Collector for Processes {
+    fn unit(&self) -> Unit {
+        Unit::None
+    }
+
+    fn scale(&self) -> Scale {
+        Scale::max(self.max)
+    }
+
+    fn sample(&mut self) -> Result<Sample> {
+        Ok(match self.count {
+            Count::Running => processes(Path::new(PROC_PATH))?.0,
+            Count::Total => processes(Path::new(PROC_PATH))?.1,
+            Count::RunningThreads => read()?.running,
+            Count::Threads => read()?.total,
+        }
+        .into())
+    }
+}
+
+/// Numbers of running and of all processes in `proc`
+fn processes(proc: &Path) -> Result<(f64, f64)> {
+    let (mut running, mut total) = (0, 0);
+    for entry in fs::read_dir(proc)
+        .with_context(|| format!("listing {}", proc.display()))?
+        .filter_map(|entry| entry.ok())
+    {
+        if !entry
+            .file_name()
+            .to_str()
+            .is_some_and(|pid| pid.bytes().all(|b| b.is_ascii_digit()))
+        {
+            continue;
+        }
+        // the process may have exited since listing
+        let Ok(stat) = fs::read_to_string(entry.path().join("stat")) else {
+            continue;
+        };
+        total += 1;
+        if state(&stat) == Some('R') {
+            running += 1;
+        }
+    }
+    Ok((running as f64, total as f64))
+}
+
+/// State of a process from `/proc/<pid>/stat`, which follows its name in parentheses. The name
+/// can contain spaces and parentheses itself.
+fn state(stat: &str) -> Option<char> {
+    stat.rsplit_once(')')?.1.trim_start().chars().next()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_loadavg() {
+        let loadavg = parse("0.52 0.58 0.59 2/1234 5678\n").unwrap();
+        assert_eq!(loadavg.averages, [0.52, 0.58, 0.59]);
+        assert_eq!(loadavg.running, 2.);
+        assert_eq!(loadavg.total, 1234.);
+    }
+
+    #[test]
+    fn counts_processes() {
+        let proc = tempfile::tempdir().unwrap();
+        for (pid, stat) in [
+            ("1", "1 (systemd) S 0 1 1 0 -1"),
+            ("42", "42 (my (weird) name) R 1 42 42 0 -1"),
+            ("43", "43 (cc1) R 42 42 42 0 -1"),
+            ("44", "44 (kworker/0:1) I 2 0 0 0 -1"),
+        ] {
+            fs::create_dir(proc.path().join(pid)).unwrap();
+            fs::write(proc.path().join(pid).join("stat"), stat).unwrap();
+        }
+        // exited while listing
+        fs::create_dir(proc.path().join("45")).unwrap();
+        fs::create_dir(proc.path().join("self")).unwrap();
+        fs::write(proc.path().join("self/stat"), "1 (systemd) R").unwrap();
+
+        assert_eq!(processes(proc.path()).unwrap(), (2., 4.));
+    }
+
+    #[test]
+    fn rejects_unexpected_loadavg() {
+        assert!(parse("").is_err());
+        assert!(parse("0.52 0.58 0.59").is_err());
+        assert!(parse("0.52 0.58 0.59 1234 5678").is_err());
+        assert!(parse("0.52 high 0.59 2/1234 5678").is_err());
+    }
+}
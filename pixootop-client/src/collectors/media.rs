@@ -0,0 +1,106 @@
+use std::process::{Command, Stdio};
+
+use anyhow::{Context as _, Result};
+
+use super::{Collector, Sample, Unavailable};
+
+const FORMAT: &str = "{{status}}\t{{position}}\t{{mpris:length}}\t{{artist}}\t{{title}}";
+
+/// The track a media player is playing, read over MPRIS through `playerctl`. The value is the
+/// position in percent of the track's length.
+pub struct Media {
+    player: Option<String>,
+}
+
+impl Media {
+    pub fn new(player: Option<String>) -> Result<Self> {
+        let installed = Command::new("playerctl")
+            .arg("--version")
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status()
+            .is_ok_and(|status| status.success());
+        if !installed {
+            Err(Unavailable("playerctl is not installed"))?;
+        }
+        Ok(Self { player })
+    }
+}
+
+impl Collector for Media {
+    fn sample(&mut self) -> Result<Sample> {
+        let mut command = Command::new("playerctl");
+        if let Some(player) = &self.player {
+            command.arg(format!("--player={player}"));
+        }
+        let output = command
+            .args(["metadata", "--format", FORMAT])
+            .stdin(Stdio::null())
+            .stderr(Stdio::null())
+            .output()
+            .context("running playerctl")?;
+        // playerctl fails if no player is running, which just means nothing is playing
+        if !output.status.success() {
+            return Ok(Sample::default());
+        }
+        parse(&String::from_utf8_lossy(&output.stdout))
+    }
+}
+
+fn parse(output: &str) -> Result<Sample> {
+    let mut fields = output.trim_end_matches('\n').split('\t');
+    let mut next = || fields.next().context("unexpected playerctl output");
+    let status = next()?;
+    let position = next()?.parse::<f64>().unwrap_or(0.);
+    let length = next()?.parse::<f64>().unwrap_or(0.);
+    let artist = next()?;
+    let title = next()?;
+    if status == "Stopped" {
+        return Ok(Sample::default());
+    }
+    Ok(Sample {
+        value: match length > 0. {
+            true => position / length * 100.,
+            false => 0.,
+        },
+        playing: Some(status == "Playing"),
+        text: Some(match artist.is_empty() {
+            true => title.to_string(),
+            false => format!("{artist} - {title}"),
+        }),
+        ..Sample::default()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_playing_track() {
+        let sample = parse("Playing\t30000000\t120000000\tArtist\tTitle\n").unwrap();
+        assert_eq!(sample.value, 25.);
+        assert_eq!(sample.playing, Some(true));
+        assert_eq!(sample.text.as_deref(), Some("Artist - Title"));
+    }
+
+    #[test]
+    fn parses_paused_track_without_artist_or_length() {
+        let sample = parse("Paused\t5000000\t\t\tStream\n").unwrap();
+        assert_eq!(sample.value, 0.);
+        assert_eq!(sample.playing, Some(false));
+        assert_eq!(sample.text.as_deref(), Some("Stream"));
+    }
+
+    #[test]
+    fn parses_stopped_player_as_nothing_playing() {
+        let sample = parse("Stopped\t0\t120000000\tArtist\tTitle\n").unwrap();
+        assert_eq!(sample.playing, None);
+        assert_eq!(sample.text, None);
+    }
+
+    #[test]
+    fn rejects_missing_fields() {
+        assert!(parse("Playing\t0\n").is_err());
+    }
+}
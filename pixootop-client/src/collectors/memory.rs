@@ -0,0 +1,85 @@
+use std::fs;
+
+use anyhow::{Context as _, Result};
+use log::debug;
+use sysinfo::{MemoryRefreshKind, RefreshKind, System};
+
+use super::{Collector, Sample, Unavailable};
+
+const MEMINFO_PATH: &str = "/proc/meminfo";
+
+/// Used RAM in percent. The used part and the page cache and buffers are sent along as parts,
+/// for a stacked bar.
+pub struct Memory {
+    sys: System,
+}
+
+impl Memory {
+    pub fn new() -> Self {
+        Self { sys: System::new() }
+    }
+}
+
+impl Collector for Memory {
+    fn sample(&mut self) -> Result<Sample> {
+        self.sys.refresh_specifics(
+            RefreshKind::nothing().with_memory(MemoryRefreshKind::nothing().with_ram()),
+        );
+        let total = self.sys.total_memory() as f64;
+        let used = self.sys.used_memory() as f64 / total;
+        // the used share is still worth showing without the parts
+        let parts = cache_bytes()
+            .inspect_err(|err| debug!("sending no memory parts: {err:#}"))
+            .ok()
+            // reclaimable memory can be counted as both used and cached
+            .map(|cache| vec![used, (cache / total).min(1. - used)]);
+        Ok(Sample {
+            value: used * 100.,
+            parts,
+            ..Sample::default()
+        })
+    }
+}
+
+/// Bytes used for buffers, the page cache, and reclaimable kernel caches.
+fn cache_bytes() -> Result<f64> {
+    let meminfo =
+        fs::read_to_string(MEMINFO_PATH).with_context(|| format!("reading {MEMINFO_PATH}"))?;
+    // lines like `Cached:          1234567 kB`
+    Ok(meminfo
+        .lines()
+        .filter_map(|line| {
+            let (key, value) = line.split_once(':')?;
+            matches!(key, "Buffers" | "Cached" | "SReclaimable")
+                .then(|| value.trim().strip_suffix(" kB")?.parse::<f64>().ok())
+                .flatten()
+        })
+        .sum::<f64>()
+        * 1024.)
+}
+
+/// Used swap in percent.
+pub struct Swap {
+    sys: System,
+}
+
+impl Swap {
+    pub fn new() -> Result<Self> {
+        let sys = System::new_with_specifics(
+            RefreshKind::nothing().with_memory(MemoryRefreshKind::nothing().with_swap()),
+        );
+        if sys.total_swap() == 0 {
+            Err(Unavailable("no swap configured"))?;
+        }
+        Ok(Self { sys })
+    }
+}
+
+impl Collector for Swap {
+    fn sample(&mut self) -> Result<Sample> {
+        self.sys.refresh_specifics(
+            RefreshKind::nothing().with_memory(MemoryRefreshKind::nothing().with_swap()),
+        );
+        Ok((self.sys.used_swap() as f64 / self.sys.total_swap() as f64 * 100.).into())
+    }
+}
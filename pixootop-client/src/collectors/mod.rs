@@ -0,0 +1,700 @@
+use std::{
+    fmt,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use anyhow::{Context as _, Result};
+
+mod audio;
+mod battery;
+pub mod cgroup;
+mod command;
+pub mod cpu;
+pub mod disk;
+mod filesystem;
+pub mod gpu;
+mod hwmon;
+pub mod load;
+mod media;
+mod memory;
+pub mod network;
+pub mod pressure;
+pub mod privacy;
+pub mod scaling;
+mod spectrum;
+mod systemd;
+pub mod top;
+
+const PROC_PATH: &str = "/proc";
+
+/// A source of one metric. To add a new one, implement this trait in a new module and add a
+/// variant for it to [`CollectorKind`], named in [`CollectorKind::default_name`].
+pub trait Collector {
+    fn unit(&self) -> Unit {
+        Unit::Percent
+    }
+
+    /// Range of values corresponding to an empty and a full bar
+    fn scale(&self) -> Scale {
+        Scale::PERCENT
+    }
+
+    /// Speed of the underlying link in bytes per second, for [`scaling::Scaling::LinkSpeed`]
+    fn link_speed(&self) -> Option<f64> {
+        None
+    }
+
+    fn sample(&mut self) -> Result<Sample>;
+}
+
+/// Error for collectors of hardware that this machine doesn't have. Their metric is left out
+/// instead of being shown as missing.
+#[derive(Debug)]
+pub struct Unavailable(pub &'static str);
+
+impl fmt::Display for Unavailable {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.0)
+    }
+}
+
+impl std::error::Error for Unavailable {}
+
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Unit {
+    None,
+    Percent,
+    Bytes,
+    BytesPerSecond,
+    Celsius,
+    Hertz,
+    Rpm,
+    Watts,
+    Seconds,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Scale {
+    pub min: f64,
+    pub max: f64,
+}
+
+impl Scale {
+    pub const PERCENT: Self = Self::max(100.);
+
+    pub const fn max(max: f64) -> Self {
+        Self { min: 0., max }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Sample {
+    pub value: f64,
+    /// Whether an audio device is muted, for collectors of audio devices
+    pub muted: Option<bool>,
+    /// Whether media is playing rather than paused, for media players
+    pub playing: Option<bool>,
+    /// Whether a battery is charging, for batteries
+    pub charging: Option<bool>,
+    /// Text to show along with the value, like the title of the playing track
+    pub text: Option<String>,
+    /// Values of the parts of the metric as fractions of a full bar, like the frequency bands of
+    /// an audio spectrum or the cores of a CPU
+    pub parts: Option<Vec<f64>>,
+}
+
+impl From<f64> for Sample {
+    fn from(value: f64) -> Self {
+        Self {
+            value,
+            ..Self::default()
+        }
+    }
+}
+
+/// An RGB color, written as `"#rrggbb"` in the config
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(try_from = "String")]
+pub struct Color(pub [u8; 3]);
+
+impl TryFrom<String> for Color {
+    type Error = anyhow::Error;
+
+    fn try_from(str: String) -> Result<Self> {
+        let hex = str
+            .strip_prefix('#')
+            .filter(|hex| hex.len() == 6 && hex.bytes().all(|b| b.is_ascii_hexdigit()))
+            .context("color must be written as #rrggbb")?;
+        let mut color = [0; 3];
+        for (i, channel) in color.iter_mut().enumerate() {
+            *channel = u8::from_str_radix(&hex[i * 2..i * 2 + 2], 16)
+                .context("color must be hexadecimal")?;
+        }
+        Ok(Self(color))
+    }
+}
+
+#[derive(Debug, Clone, serde::Deserialize)]
+#[serde(try_from = "RawCollectorConfig")]
+pub struct CollectorConfig {
+    /// Overrides the name of the metric
+    pub name: Option<String>,
+    /// Overrides the color the server uses for the metric
+    pub color: Option<Color>,
+    pub schedule: Schedule,
+    /// How the value is mapped onto the bar
+    #[serde(default)]
+    pub scale: scaling::Scaling,
+    pub kind: CollectorKind,
+}
+
+/// [`CollectorConfig`] with the fields of the collector kind left as a table. Flattened fields
+/// don't support `deny_unknown_fields`, so the kind is deserialized from whatever fields are
+/// left, which rejects unknown ones.
+#[derive(serde::Deserialize)]
+struct RawCollectorConfig {
+    name: Option<String>,
+    color: Option<Color>,
+    #[serde(default, with = "humantime_serde")]
+    interval: Option<Duration>,
+    #[serde(default, with = "humantime_serde")]
+    timeout: Option<Duration>,
+    #[serde(default)]
+    scale: scaling::Scaling,
+    #[serde(flatten)]
+    kind: toml::Table,
+}
+
+impl TryFrom<RawCollectorConfig> for CollectorConfig {
+    type Error = toml::de::Error;
+
+    fn try_from(raw: RawCollectorConfig) -> Result<Self, Self::Error> {
+        let kind = toml::Value::Table(raw.kind).try_into()?;
+        Ok(Self {
+            name: raw.name,
+            color: raw.color,
+            schedule: Schedule::new(&kind, raw.interval, raw.timeout),
+            scale: raw.scale,
+            kind,
+        })
+    }
+}
+
+impl CollectorConfig {
+    /// Splits collectors which report a separate metric per device into one collector per
+    /// device.
+    pub fn expand(self) -> Vec<Self> {
+        let (CollectorKind::Gpu {
+            card: selector,
+            aggregate: gpu::Aggregate::Each,
+        }
+        | CollectorKind::Vram {
+            card: selector,
+            aggregate: gpu::Aggregate::Each,
+        }
+        | CollectorKind::GpuPower {
+            card: selector,
+            aggregate: gpu::Aggregate::Each,
+            ..
+        }) = &self.kind
+        else {
+            return vec![self];
+        };
+        let Ok(cards) = gpu::select(Path::new(gpu::DRM_PATH), selector.as_deref()) else {
+            return vec![self];
+        };
+        let base_name = self
+            .name
+            .clone()
+            .unwrap_or_else(|| self.kind.default_name().to_string());
+        cards
+            .into_iter()
+            .map(|card| {
+                let card_name = card.file_name().unwrap_or_default().to_string_lossy();
+                let card = Some(card.to_string_lossy().into_owned());
+                let aggregate = gpu::Aggregate::Single;
+                Self {
+                    name: Some(format!("{base_name}.{card_name}")),
+                    kind: match self.kind {
+                        CollectorKind::Gpu { .. } => CollectorKind::Gpu { card, aggregate },
+                        CollectorKind::Vram { .. } => CollectorKind::Vram { card, aggregate },
+                        CollectorKind::GpuPower { max, .. } => CollectorKind::GpuPower {
+                            card,
+                            aggregate,
+                            max,
+                        },
+                        _ => unreachable!(),
+                    },
+                    ..self.clone()
+                }
+            })
+            .collect()
+    }
+}
+
+impl From<CollectorKind> for CollectorConfig {
+    fn from(kind: CollectorKind) -> Self {
+        Self {
+            name: None,
+            color: None,
+            schedule: Schedule::new(&kind, None, None),
+            scale: scaling::Scaling::default(),
+            kind,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Schedule {
+    /// Time between two samples
+    pub interval: Duration,
+    /// Age after which a sample is considered missing, e.g. because reading it hangs
+    pub timeout: Duration,
+}
+
+impl Schedule {
+    /// Uses the default interval of the collector kind if none is set, and a timeout that
+    /// leaves room for a few samples to go missing if none is set.
+    fn new(kind: &CollectorKind, interval: Option<Duration>, timeout: Option<Duration>) -> Self {
+        let interval = interval.unwrap_or_else(|| kind.default_interval());
+        Self {
+            interval,
+            timeout: timeout.unwrap_or_else(|| (interval * 3).max(Duration::from_secs(1))),
+        }
+    }
+}
+
+#[derive(Debug, Clone, serde::Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+pub enum CollectorKind {
+    Cpu {
+        #[serde(default)]
+        aggregate: cpu::Aggregate,
+    },
+    CpuFrequency {
+        #[serde(default)]
+        aggregate: cpu::Aggregate,
+    },
+    Load {
+        /// `1m`, `5m`, or `15m`
+        #[serde(default)]
+        period: load::Period,
+    },
+    Processes {
+        #[serde(default)]
+        count: load::Count,
+        /// Processes or threads for a full bar, the number of cores for running ones, 500 for
+        /// all processes and 1000 for all threads if not set
+        max: Option<f64>,
+    },
+    // variants without fields have braces, as unknown fields of unit variants are ignored
+    Memory {},
+    Swap {},
+    Pressure {
+        resource: pressure::Resource,
+        /// `10s`, `60s`, or `300s`
+        #[serde(default)]
+        window: pressure::Window,
+        /// Count the time in which all tasks were stalled instead of at least one
+        #[serde(default)]
+        full: bool,
+    },
+    Gpu {
+        /// Path, name like `card1`, PCI address, or PCI ID like `1002:73bf` of the DRM card
+        card: Option<String>,
+        #[serde(default)]
+        aggregate: gpu::Aggregate,
+    },
+    Vram {
+        /// Path, name like `card1`, PCI address, or PCI ID like `1002:73bf` of the DRM card
+        card: Option<String>,
+        #[serde(default)]
+        aggregate: gpu::Aggregate,
+    },
+    GpuPower {
+        /// Path, name like `card1`, PCI address, or PCI ID like `1002:73bf` of the DRM card
+        card: Option<String>,
+        #[serde(default)]
+        aggregate: gpu::Aggregate,
+        /// Watts for a full bar, the GPU's power limit if not set
+        max: Option<f64>,
+    },
+    Hwmon {
+        /// Name of the hwmon chip, like `k10temp`, `coretemp`, or `amdgpu`
+        chip: String,
+        sensor: hwmon::SensorKind,
+        /// Label like `Tctl` or `junction`, or channel like `temp2`, the first one if not set
+        label: Option<String>,
+        min: Option<f64>,
+        max: Option<f64>,
+    },
+    Volume {
+        #[serde(default)]
+        backend: audio::Backend,
+        /// Name of the sink for pulse, or node ID for PipeWire, the default sink if not set
+        device: Option<String>,
+    },
+    Microphone {
+        #[serde(default)]
+        backend: audio::Backend,
+        /// Name of the source for pulse, or node ID for PipeWire, the default source if not set
+        device: Option<String>,
+    },
+    Battery {
+        /// Name of the battery like `BAT0`, all batteries combined if not set
+        battery: Option<String>,
+        #[serde(default)]
+        reading: battery::Reading,
+        /// Seconds or watts for a full bar, 10 hours or 50 W if not set
+        max: Option<f64>,
+    },
+    Media {
+        /// Name of the MPRIS player like `spotify`, the one `playerctl` picks if not set
+        player: Option<String>,
+    },
+    Spectrum {},
+    TopProcess {
+        by: top::By,
+    },
+    Privacy {
+        device: privacy::Device,
+    },
+    Cgroup {
+        /// Paths of cgroups below `/sys/fs/cgroup` which may contain glob patterns, like
+        /// `system.slice/docker-*.scope`, or `containers` for all running containers. Usage of
+        /// all matching cgroups is summed.
+        #[serde(default, alias = "cgroups")]
+        cgroup: cgroup::Cgroups,
+        resource: cgroup::Resource,
+        /// Value for a full bar, all cores, the total RAM, or 100 MB/s if not set
+        max: Option<f64>,
+    },
+    FailedUnits {
+        /// Count the units of the user's service manager instead of the system's
+        #[serde(default)]
+        user: bool,
+        /// Failed units for a full bar
+        #[serde(default = "default_failed_units_max")]
+        max: f64,
+    },
+    JournalErrors {
+        /// Read the user's journal instead of the system's
+        #[serde(default)]
+        user: bool,
+        /// Journal file or directory to read instead of the local journal
+        journal: Option<PathBuf>,
+        /// How far back to count entries
+        #[serde(default = "default_journal_window", with = "humantime_serde")]
+        window: Duration,
+        /// Entries per minute for a full bar
+        #[serde(default = "default_journal_errors_max")]
+        max: f64,
+    },
+    Network {
+        /// Interface names, glob patterns like `wl*`, or `default` for the interface of the
+        /// default route. Traffic of all matching interfaces is summed.
+        #[serde(default, alias = "interfaces")]
+        interface: network::Interfaces,
+        direction: network::Direction,
+        /// Bytes per second for a full bar
+        #[serde(default = "default_network_max")]
+        max: f64,
+    },
+    Disk {
+        /// Name of the block device in `/proc/diskstats`, like `nvme0n1` or `sda1`
+        device: String,
+        direction: disk::Direction,
+        /// Bytes per second for a full bar
+        #[serde(default = "default_disk_max")]
+        max: f64,
+    },
+    Filesystem {
+        /// Where the filesystem is mounted
+        mount_point: PathBuf,
+    },
+    Command {
+        /// Program and its arguments
+        command: Vec<String>,
+        #[serde(default)]
+        mode: command::Mode,
+        /// Dot separated path to the value if the output is a JSON object
+        field: Option<String>,
+        #[serde(default = "default_command_unit")]
+        unit: Unit,
+        #[serde(default)]
+        min: f64,
+        #[serde(default = "default_command_max")]
+        max: f64,
+    },
+}
+
+pub fn default_network_max() -> f64 {
+    2_000_000.
+}
+
+pub(super) fn default_disk_max() -> f64 {
+    100_000_000.
+}
+
+fn default_failed_units_max() -> f64 {
+    1.
+}
+
+fn default_journal_window() -> Duration {
+    Duration::from_secs(60)
+}
+
+fn default_journal_errors_max() -> f64 {
+    10.
+}
+
+fn default_command_unit() -> Unit {
+    Unit::None
+}
+
+fn default_command_max() -> f64 {
+    100.
+}
+
+impl CollectorKind {
+    /// Collectors which spawn or scan all processes per sample are sampled less often by
+    /// default.
+    fn default_interval(&self) -> Duration {
+        match self {
+            // the CPU usage of processes is also more meaningful over a longer interval
+            Self::Media { .. }
+            | Self::TopProcess { .. }
+            | Self::Privacy {
+                device: privacy::Device::Camera,
+            } => Duration::from_secs(1),
+            // every process has to be read to count them
+            Self::Processes {
+                count: load::Count::Running | load::Count::Total,
+                ..
+            } => Duration::from_secs(1),
+            // failed units, journal errors and filesystem usage rarely change and a second late
+            // is fine
+            Self::FailedUnits { .. } | Self::JournalErrors { .. } | Self::Filesystem { .. } => {
+                Duration::from_secs(10)
+            }
+            _ => Duration::from_millis(100),
+        }
+    }
+
+    /// Creates the collector. This is called on the thread the collector will run on, so
+    /// collectors don't have to be `Send`.
+    pub fn build(&self, schedule: &Schedule) -> Result<Box<dyn Collector>> {
+        Ok(match self {
+            Self::Cpu { aggregate } => Box::new(cpu::Cpu::new(*aggregate)),
+            Self::CpuFrequency { aggregate } => Box::new(cpu::Frequency::new(*aggregate)),
+            Self::Load { period } => Box::new(load::Load::new(*period)),
+            Self::Processes { count, max } => Box::new(load::Processes::new(*count, *max)),
+            Self::Memory {} => Box::new(memory::Memory::new()),
+            Self::Swap {} => Box::new(memory::Swap::new()?),
+            Self::Pressure {
+                resource,
+                window,
+                full,
+            } => Box::new(pressure::Pressure::new(*resource, *window, *full)?),
+            Self::Gpu { card, aggregate } => Box::new(gpu::Gpu::new(
+                Path::new(gpu::DRM_PATH),
+                card.as_deref(),
+                *aggregate,
+            )?),
+            Self::Vram { card, aggregate } => Box::new(gpu::Vram::new(
+                Path::new(gpu::DRM_PATH),
+                card.as_deref(),
+                *aggregate,
+            )?),
+            Self::GpuPower {
+                card,
+                aggregate,
+                max,
+            } => Box::new(gpu::GpuPower::new(
+                Path::new(gpu::DRM_PATH),
+                card.as_deref(),
+                *aggregate,
+                *max,
+            )?),
+            Self::Hwmon {
+                chip,
+                sensor,
+                label,
+                min,
+                max,
+            } => {
+                let default = sensor.default_scale();
+                Box::new(hwmon::Hwmon::new(
+                    Path::new(hwmon::HWMON_PATH),
+                    chip,
+                    *sensor,
+                    label.as_deref(),
+                    Scale {
+                        min: min.unwrap_or(default.min),
+                        max: max.unwrap_or(default.max),
+                    },
+                )?)
+            }
+            Self::Volume { backend, device } => Box::new(audio::Audio::new(
+                audio::Device::Sink,
+                *backend,
+                device.clone(),
+            )?),
+            Self::Microphone { backend, device } => Box::new(audio::Audio::new(
+                audio::Device::Source,
+                *backend,
+                device.clone(),
+            )?),
+            Self::Battery {
+                battery,
+                reading,
+                max,
+            } => Box::new(battery::Battery::new(
+                Path::new(battery::POWER_SUPPLY_PATH),
+                battery.as_deref(),
+                *reading,
+                *max,
+            )?),
+            Self::Media { player } => Box::new(media::Media::new(player.clone())?),
+            Self::Spectrum {} => Box::new(spectrum::Spectrum::new()?),
+            Self::TopProcess { by } => Box::new(top::TopProcess::new(*by)),
+            Self::FailedUnits { user, max } => Box::new(systemd::FailedUnits::new(*user, *max)?),
+            Self::JournalErrors {
+                user,
+                journal,
+                window,
+                max,
+            } => Box::new(systemd::JournalErrors::new(
+                *user,
+                journal.clone(),
+                *window,
+                *max,
+            )?),
+            Self::Privacy { device } => Box::new(privacy::Privacy::new(*device)?),
+            Self::Cgroup {
+                cgroup,
+                resource,
+                max,
+            } => Box::new(cgroup::Cgroup::new(cgroup.clone(), *resource, *max)?),
+            Self::Network {
+                interface,
+                direction,
+                max,
+            } => Box::new(network::Network::new(interface.clone(), *direction, *max)),
+            Self::Disk {
+                device,
+                direction,
+                max,
+            } => Box::new(disk::Disk::new(device.clone(), *direction, *max)?),
+            Self::Filesystem { mount_point } => {
+                Box::new(filesystem::Filesystem::new(mount_point.clone())?)
+            }
+            Self::Command {
+                command,
+                mode,
+                field,
+                unit,
+                min,
+                max,
+            } => Box::new(command::Command::new(
+                command.clone(),
+                *mode,
+                field.clone(),
+                *unit,
+                Scale {
+                    min: *min,
+                    max: *max,
+                },
+                schedule.timeout,
+            )?),
+        })
+    }
+
+    /// Name of the metric if the config doesn't set one, used by the server to decide how to
+    /// display it
+    pub fn default_name(&self) -> &'static str {
+        match self {
+            Self::Cpu { .. } => "cpu",
+            Self::CpuFrequency { .. } => "cpu_freq",
+            Self::Load { .. } => "load",
+            Self::Processes { count, .. } => match count {
+                load::Count::Running => "procs_running",
+                load::Count::Total => "procs",
+                load::Count::RunningThreads => "threads_running",
+                load::Count::Threads => "threads",
+            },
+            Self::Memory {} => "mem",
+            Self::Swap {} => "swap",
+            Self::Pressure { resource, .. } => match resource {
+                pressure::Resource::Cpu => "psi_cpu",
+                pressure::Resource::Memory => "psi_mem",
+                pressure::Resource::Io => "psi_io",
+            },
+            Self::Gpu { .. } => "gpu",
+            Self::Vram { .. } => "gpu_mem",
+            Self::GpuPower { .. } => "gpu_power",
+            Self::Hwmon { sensor, .. } => sensor.prefix(),
+            Self::Volume { .. } => "vol",
+            Self::Microphone { .. } => "mic",
+            Self::Battery { reading, .. } => match reading {
+                battery::Reading::Charge => "battery",
+                battery::Reading::Time => "battery_time",
+                battery::Reading::Power => "battery_power",
+            },
+            Self::Media { .. } => "media",
+            Self::Spectrum {} => "spectrum",
+            Self::TopProcess { by } => match by {
+                top::By::Cpu => "top_cpu",
+                top::By::Memory => "top_mem",
+            },
+            Self::Privacy { device } => match device {
+                privacy::Device::Camera => "camera_in_use",
+                privacy::Device::Microphone => "mic_in_use",
+            },
+            Self::Cgroup { resource, .. } => match resource {
+                cgroup::Resource::Cpu => "cgroup_cpu",
+                cgroup::Resource::Memory => "cgroup_mem",
+                cgroup::Resource::Io => "cgroup_io",
+            },
+            Self::FailedUnits { .. } => "failed_units",
+            Self::JournalErrors { .. } => "journal_errors",
+            Self::Network { direction, .. } => match direction {
+                network::Direction::Up => "net_up",
+                network::Direction::Down => "net_down",
+            },
+            Self::Disk { direction, .. } => match direction {
+                disk::Direction::Read => "disk_read",
+                disk::Direction::Write => "disk_write",
+            },
+            Self::Filesystem { .. } => "fs",
+            Self::Command { .. } => "command",
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_colors() {
+        assert_eq!(
+            Color::try_from("#ff8000".to_string()).unwrap().0,
+            [0xff, 0x80, 0x00]
+        );
+        assert_eq!(
+            Color::try_from("#A0b1C2".to_string()).unwrap().0,
+            [0xa0, 0xb1, 0xc2]
+        );
+        for color in [
+            "ff8000", "#ff800", "#ff80000", "#+f8000", "#ff80+0", "#gg8000", "#ff80é",
+        ] {
+            assert!(
+                Color::try_from(color.to_string()).is_err(),
+                "accepted {color:?}"
+            );
+        }
+    }
+}
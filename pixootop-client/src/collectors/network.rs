@@ -0,0 +1,233 @@
+use std::{fs, time::Instant};
+
+use anyhow::{Context as _, Result, bail};
+use sysinfo::Networks;
+
+use super::{Collector, Sample, Scale, Unit};
+use crate::{average::Averaged, glob};
+
+const ROUTE_PATH: &str = "/proc/net/route";
+const SYS_NET_PATH: &str = "/sys/class/net";
+/// Selects the interface of the default route
+pub const DEFAULT_ROUTE: &str = "default";
+
+#[derive(Debug, Clone, Copy, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Direction {
+    Up,
+    Down,
+}
+
+/// One interface selector or a list of them, so a single one can be written as a plain string
+#[derive(Debug, Clone, serde::Deserialize)]
+#[serde(untagged)]
+pub enum Interfaces {
+    One(String),
+    Many(Vec<String>),
+}
+
+impl Interfaces {
+    fn selectors(&self) -> &[String] {
+        match self {
+            Self::One(selector) => std::slice::from_ref(selector),
+            Self::Many(selectors) => selectors,
+        }
+    }
+}
+
+impl Default for Interfaces {
+    fn default() -> Self {
+        Self::One(DEFAULT_ROUTE.to_string())
+    }
+}
+
+/// Traffic in bytes per second summed over all interfaces matching any of the selectors.
+/// Interfaces which come and go, like VPNs or docking stations, are picked up on every sample.
+pub struct Network {
+    networks: Networks,
+    interfaces: Interfaces,
+    direction: Direction,
+    max: f64,
+    /// Interfaces which matched in the last sample
+    matched: Vec<String>,
+    /// When the interfaces were last refreshed, as their counters are relative to it
+    refreshed: Option<Instant>,
+    data: Averaged<f64, 10>,
+}
+
+impl Network {
+    pub fn new(interfaces: Interfaces, direction: Direction, max: f64) -> Self {
+        Self {
+            networks: Networks::new(),
+            interfaces,
+            direction,
+            max,
+            matched: Vec::new(),
+            refreshed: None,
+            data: Averaged::new(0.),
+        }
+    }
+
+    /// Whether an interface matches a selector, which is either [`DEFAULT_ROUTE`], a glob
+    /// pattern like `wl*`, or a name.
+    fn matches(selector: &str, name: &str, default: Option<&str>) -> bool {
+        match selector {
+            DEFAULT_ROUTE => default == Some(name),
+            _ => glob::matches(selector, name),
+        }
+    }
+}
+
+impl Collector for Network {
+    fn unit(&self) -> Unit {
+        Unit::BytesPerSecond
+    }
+
+    fn scale(&self) -> Scale {
+        Scale::max(self.max)
+    }
+
+    /// Sum of the speeds of the matched interfaces, if all of them report one
+    fn link_speed(&self) -> Option<f64> {
+        self.matched
+            .iter()
+            .map(|name| {
+                let speed = fs::read_to_string(format!("{SYS_NET_PATH}/{name}/speed")).ok()?;
+                // in Mbit/s, -1 if unknown
+                let mbits = speed
+                    .trim()
+                    .parse::<f64>()
+                    .ok()
+                    .filter(|&mbits| mbits > 0.)?;
+                Some(mbits * 1_000_000. / 8.)
+            })
+            .sum::<Option<f64>>()
+            .filter(|&speed| speed > 0.)
+    }
+
+    fn sample(&mut self) -> Result<Sample> {
+        self.networks.refresh(true);
+        let now = Instant::now();
+        let elapsed = self
+            .refreshed
+            .replace(now)
+            .map(|time| now.duration_since(time).as_secs_f64());
+        let selectors = self.interfaces.selectors();
+        let default = match selectors.iter().any(|selector| selector == DEFAULT_ROUTE) {
+            true => default_route_interface(
+                &fs::read_to_string(ROUTE_PATH).with_context(|| format!("reading {ROUTE_PATH}"))?,
+            ),
+            false => None,
+        };
+        self.matched.clear();
+        let mut bytes = 0;
+        for (name, net) in &self.networks {
+            if !selectors
+                .iter()
+                .any(|selector| Self::matches(selector, name, default.as_deref()))
+            {
+                continue;
+            }
+            self.matched.push(name.clone());
+            bytes += match self.direction {
+                Direction::Up => net.transmitted(),
+                Direction::Down => net.received(),
+            };
+        }
+        if self.matched.is_empty() {
+            bail!("no network interface matches {selectors:?}");
+        }
+        let rate = match elapsed {
+            Some(elapsed) if elapsed > 0. => bytes as f64 / elapsed,
+            _ => 0.,
+        };
+        Ok(self.data.next(rate).into())
+    }
+}
+
+/// Interface of the default route with the lowest metric in `/proc/net/route`, if there is one.
+fn default_route_interface(routes: &str) -> Option<String> {
+    // Iface, Destination, Gateway, Flags, RefCnt, Use, Metric, Mask, ...
+    routes
+        .lines()
+        .skip(1)
+        .map(|line| line.split_whitespace().collect::<Vec<_>>())
+        .filter(|fields| fields.len() > 7 && fields[1] == "00000000" && fields[7] == "00000000")
+        .min_by_key(|fields| fields[6].parse::<u32>().unwrap_or(u32::MAX))
+        .map(|fields| fields[0].to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Wi-Fi and a docking station's Ethernet, both with a default route, and a VPN
+    const ROUTES: &str = "\
+Iface\tDestination\tGateway \tFlags\tRefCnt\tUse\tMetric\tMask\t\tMTU\tWindow\tIRTT
+wlan0\t00000000\t0101A8C0\t0003\t0\t0\t600\t00000000\t0\t0\t0
+enp0s31f6\t00000000\t0100A8C0\t0003\t0\t0\t100\t00000000\t0\t0\t0
+wg0\t0000080A\t00000000\t0001\t0\t0\t0\t0000FFFF\t0\t0\t0
+wlan0\t0001A8C0\t00000000\t0001\t0\t0\t600\t00FFFFFF\t0\t0\t0
+";
+
+    #[test]
+    fn finds_default_route_with_lowest_metric() {
+        assert_eq!(
+            default_route_interface(ROUTES).as_deref(),
+            Some("enp0s31f6")
+        );
+        let wifi_only: String = ROUTES
+            .lines()
+            .filter(|line| !line.starts_with("enp"))
+            .map(|line| format!("{line}\n"))
+            .collect();
+        assert_eq!(
+            default_route_interface(&wifi_only).as_deref(),
+            Some("wlan0")
+        );
+        let no_default: String = ROUTES.lines().take(1).collect();
+        assert_eq!(default_route_interface(&no_default), None);
+        assert_eq!(default_route_interface(""), None);
+    }
+
+    #[test]
+    fn matches_selectors() {
+        assert!(Network::matches("wl*", "wlan0", None));
+        assert!(!Network::matches("wl*", "enp0s31f6", Some("wlan0")));
+        assert!(Network::matches("enp0s31f6", "enp0s31f6", None));
+        assert!(Network::matches(DEFAULT_ROUTE, "wlan0", Some("wlan0")));
+        assert!(!Network::matches(DEFAULT_ROUTE, "enp0s31f6", Some("wlan0")));
+        assert!(!Network::matches(DEFAULT_ROUTE, "wlan0", None));
+    }
+
+    #[test]
+    fn parses_interface_lists() {
+        #[derive(serde::Deserialize)]
+        struct Config {
+            #[serde(default)]
+            interface: Interfaces,
+        }
+        let selectors = |config: &str| {
+            toml::from_str::<Config>(config)
+                .unwrap()
+                .interface
+                .selectors()
+                .to_vec()
+        };
+        assert_eq!(selectors(""), [DEFAULT_ROUTE]);
+        assert_eq!(selectors(r#"interface = "wl*""#), ["wl*"]);
+        assert_eq!(
+            selectors(r#"interface = ["default", "wg0"]"#),
+            [DEFAULT_ROUTE, "wg0"]
+        );
+        let matching: Vec<_> = ["wlan0", "enp0s31f6", "wg0", "lo"]
+            .into_iter()
+            .filter(|name| {
+                selectors(r#"interface = ["default", "wg*"]"#)
+                    .iter()
+                    .any(|selector| Network::matches(selector, name, Some("enp0s31f6")))
+            })
+            .collect();
+        assert_eq!(matching, ["enp0s31f6", "wg0"]);
+    }
+}
@@ -0,0 +1,113 @@
+use std::{fs, path::PathBuf};
+
+use anyhow::{Context as _, Result};
+
+use super::{Collector, Sample, Unavailable};
+
+const PRESSURE_PATH: &str = "/proc/pressure";
+
+#[derive(Debug, Clone, Copy, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Resource {
+    Cpu,
+    Memory,
+    Io,
+}
+
+#[derive(Debug, Clone, Copy, Default, serde::Deserialize)]
+pub enum Window {
+    #[default]
+    #[serde(rename = "10s")]
+    TenSeconds,
+    #[serde(rename = "60s")]
+    OneMinute,
+    #[serde(rename = "300s")]
+    FiveMinutes,
+}
+
+/// Pressure stall information: the share of time in which some or all tasks were stalled
+/// waiting for a resource, in percent.
+pub struct Pressure {
+    path: PathBuf,
+    window: Window,
+    full: bool,
+}
+
+impl Pressure {
+    pub fn new(resource: Resource, window: Window, full: bool) -> Result<Self> {
+        let path = PathBuf::from(PRESSURE_PATH).join(match resource {
+            Resource::Cpu => "cpu",
+            Resource::Memory => "memory",
+            Resource::Io => "io",
+        });
+        if !path.exists() {
+            Err(Unavailable(
+                "kernel doesn't report pressure stall information",
+            ))?;
+        }
+        Ok(Self { path, window, full })
+    }
+}
+
+impl Collector for Pressure {
+    fn sample(&mut self) -> Result<Sample> {
+        let pressure = fs::read_to_string(&self.path)
+            .with_context(|| format!("reading {}", self.path.display()))?;
+        let value = parse(&pressure, self.window, self.full)
+            .with_context(|| format!("parsing {}", self.path.display()))?;
+        Ok(value.into())
+    }
+}
+
+/// Parses the average of `window` from the `some` or `full` line of a file in `/proc/pressure`,
+/// like `some avg10=0.12 avg60=0.05 avg300=0.01 total=123456`.
+fn parse(pressure: &str, window: Window, full: bool) -> Result<f64> {
+    let line = match full {
+        true => "full",
+        false => "some",
+    };
+    let key = match window {
+        Window::TenSeconds => "avg10",
+        Window::OneMinute => "avg60",
+        Window::FiveMinutes => "avg300",
+    };
+    pressure
+        .lines()
+        .find_map(|l| l.strip_prefix(line)?.strip_prefix(' '))
+        .with_context(|| format!("no {line:?} line"))?
+        .split_whitespace()
+        .find_map(|field| field.strip_prefix(key)?.strip_prefix('='))
+        .with_context(|| format!("no {key}"))?
+        .parse()
+        .context("parsing pressure")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MEMORY: &str = "\
+some avg10=1.53 avg60=0.87 avg300=0.25 total=7654321
+full avg10=0.42 avg60=0.19 avg300=0.05 total=1234567
+";
+
+    #[test]
+    fn parses_windows_and_lines() {
+        assert_eq!(parse(MEMORY, Window::TenSeconds, false).unwrap(), 1.53);
+        assert_eq!(parse(MEMORY, Window::OneMinute, false).unwrap(), 0.87);
+        assert_eq!(parse(MEMORY, Window::FiveMinutes, false).unwrap(), 0.25);
+        assert_eq!(parse(MEMORY, Window::TenSeconds, true).unwrap(), 0.42);
+        assert_eq!(parse(MEMORY, Window::FiveMinutes, true).unwrap(), 0.05);
+    }
+
+    #[test]
+    fn rejects_missing_fields() {
+        // older kernels have no full line for the CPU
+        let cpu = "some avg10=3.00 avg60=2.00 avg300=1.00 total=42\n";
+        assert_eq!(parse(cpu, Window::TenSeconds, false).unwrap(), 3.);
+        assert!(parse(cpu, Window::TenSeconds, true).is_err());
+        assert!(parse("some avg60=2.00 total=42", Window::TenSeconds, false).is_err());
+        assert!(parse("some avg10=high", Window::TenSeconds, false).is_err());
+        assert!(parse("", Window::TenSeconds, false).is_err());
+    }
+}
@@ -0,0 +1,180 @@
+use std::{collections::BTreeSet, fs, path::Path};
+
+use anyhow::{Context as _, Result};
+use pulsectl::controllers::{AppControl, DeviceControl, SourceController};
+
+use super::{Collector, PROC_PATH, Sample, Scale, Unit};
+
+#[derive(Debug, Clone, Copy, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Device {
+    /// Video devices in `/dev/video*`
+    Camera,
+    /// Recording streams of pulse or PipeWire sources
+    Microphone,
+}
+
+/// Number of processes using a camera, or of streams recording from a microphone. The names of
+/// the processes or applications are sent along as text.
+pub struct Privacy {
+    /// Only for [`Device::Microphone`]
+    controller: Option<SourceController>,
+}
+
+impl Privacy {
+    pub fn new(device: Device) -> Result<Self> {
+        let controller = match device {
+            Device::Camera => None,
+            Device::Microphone => {
+                Some(SourceController::create().context("creating source controller")?)
+            }
+        };
+        Ok(Self { controller })
+    }
+}
+
+impl Collector for Privacy {
+    fn unit(&self) -> Unit {
+        Unit::None
+    }
+
+    fn scale(&self) -> Scale {
+        Scale::max(1.)
+    }
+
+    fn sample(&mut self) -> Result<Sample> {
+        let users = match &mut self.controller {
+            Some(controller) => recording_applications(controller)?,
+            None => camera_processes(Path::new(PROC_PATH))?,
+        };
+        Ok(Sample {
+            value: users.len() as f64,
+            text: (!users.is_empty()).then(|| Vec::from_iter(users).join(", ")),
+            ..Sample::default()
+        })
+    }
+}
+
+/// Names of the processes which have a video device open. Only processes whose file descriptors
+/// can be read, usually those of the same user, are found.
+fn camera_processes(proc: &Path) -> Result<BTreeSet<String>> {
+    let mut processes = BTreeSet::new();
+    for entry in fs::read_dir(proc)
+        .with_context(|| format!("listing {}", proc.display()))?
+        .filter_map(|entry| entry.ok())
+    {
+        let Some(pid) = entry.file_name().to_str().map(str::to_string) else {
+            continue;
+        };
+        if !pid.bytes().all(|b| b.is_ascii_digit()) {
+            continue;
+        }
+        // processes can exit while being read, and those of other users can't be read
+        let Ok(fds) = fs::read_dir(entry.path().join("fd")) else {
+            continue;
+        };
+        let uses_camera = fds.filter_map(|fd| fd.ok()).any(|fd| {
+            fs::read_link(fd.path()).is_ok_and(|target| {
+                target.starts_with("/dev")
+                    && target
+                        .file_name()
+                        .and_then(|name| name.to_str())
+                        .is_some_and(|name| name.starts_with("video"))
+            })
+        });
+        if uses_camera {
+            let comm = fs::read_to_string(entry.path().join("comm"));
+            processes.insert(comm.map_or(pid, |comm| comm.trim().to_string()));
+        }
+    }
+    Ok(processes)
+}
+
+/// Names of the applications recording from a source. Streams from monitor sources, which record
+/// what is playing rather than a microphone, and paused streams are left out.
+fn recording_applications(controller: &mut SourceController) -> Result<BTreeSet<String>> {
+    let monitors = controller
+        .list_devices()
+        .context("listing sources")?
+        .into_iter()
+        .filter(|source| source.monitor.is_some())
+        .map(|source| source.index)
+        .collect::<BTreeSet<_>>();
+    let streams = controller
+        .list_applications()
+        .context("listing source outputs")?
+        .into_iter()
+        .map(|stream| Stream {
+            name: stream
+                .proplist
+                .get_str("application.name")
+                .or(stream.name)
+                .unwrap_or_else(|| stream.index.to_string()),
+            source: stream.connection_id,
+            corked: stream.corked,
+        });
+    Ok(recording(&monitors, streams))
+}
+
+/// A source output, which records from the source with the index `source`
+struct Stream {
+    name: String,
+    source: u32,
+    corked: bool,
+}
+
+fn recording(monitors: &BTreeSet<u32>, streams: impl Iterator<Item = Stream>) -> BTreeSet<String> {
+    streams
+        .filter(|stream| !stream.corked && !monitors.contains(&stream.source))
+        .map(|stream| stream.name)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::os::unix::fs::symlink;
+
+    use super::*;
+
+    fn process(proc: &Path, pid: &str, comm: Option<&str>, fds: &[&str]) {
+        let dir = proc.join(pid);
+        fs::create_dir_all(dir.join("fd")).unwrap();
+        if let Some(comm) = comm {
+            fs::write(dir.join("comm"), format!("{comm}\n")).unwrap();
+        }
+        for (fd, target) in fds.iter().enumerate() {
+            symlink(target, dir.join("fd").join(fd.to_string())).unwrap();
+        }
+    }
+
+    #[test]
+    fn finds_processes_using_a_camera() {
+        let proc = tempfile::tempdir().unwrap();
+        let proc = proc.path();
+        process(proc, "100", Some("zoom"), &["/dev/null", "/dev/video0"]);
+        process(proc, "200", None, &["/dev/video2"]);
+        process(proc, "300", Some("cat"), &["/dev/null", "/home/video.mp4"]);
+        fs::create_dir(proc.join("self")).unwrap();
+
+        let processes = camera_processes(proc).unwrap();
+        assert_eq!(Vec::from_iter(processes), ["200", "zoom"]);
+    }
+
+    #[test]
+    fn leaves_out_monitors_and_paused_streams() {
+        let stream = |name: &str, source, corked| Stream {
+            name: name.to_string(),
+            source,
+            corked,
+        };
+        let monitors = BTreeSet::from([1]);
+        let streams = [
+            stream("Firefox", 2, false),
+            stream("OBS", 1, false),
+            stream("Discord", 2, true),
+            stream("arecord", 3, false),
+        ];
+        let recording = recording(&monitors, streams.into_iter());
+        assert_eq!(Vec::from_iter(recording), ["Firefox", "arecord"]);
+    }
+}
@@ -0,0 +1,158 @@
+use std::time::{Duration, Instant};
+
+use super::Scale;
+
+/// How a value is mapped onto the length of its bar.
+#[derive(Debug, Clone, Copy, Default, serde::Deserialize)]
+#[serde(tag = "mode", rename_all = "snake_case", deny_unknown_fields)]
+pub enum Scaling {
+    /// Linear between the collector's `min` and `max`
+    #[default]
+    Fixed,
+    /// Linear up to the speed of the link, for network interfaces which report it. Falls back
+    /// to `fixed` for links without a known speed, like most Wi-Fi adapters.
+    LinkSpeed,
+    /// Linear up to the highest recent value, which decays towards `floor` over time
+    Peak {
+        /// Time after which the peak has decayed to half its value
+        #[serde(default = "default_half_life", with = "humantime_serde")]
+        half_life: Duration,
+        /// Lowest value used for a full bar, so idle noise doesn't fill it
+        #[serde(default = "default_floor")]
+        floor: f64,
+    },
+    /// Logarithmic up to the collector's `max`, so small and large values both stay visible
+    Log,
+}
+
+fn default_half_life() -> Duration {
+    Duration::from_secs(10)
+}
+
+fn default_floor() -> f64 {
+    10_000.
+}
+
+/// Applies a [`Scaling`] to consecutive samples of one collector.
+pub struct Scaler {
+    scaling: Scaling,
+    fixed: Scale,
+    peak: Option<(f64, Instant)>,
+}
+
+impl Scaler {
+    pub fn new(scaling: Scaling, fixed: Scale) -> Self {
+        Self {
+            scaling,
+            fixed,
+            peak: None,
+        }
+    }
+
+    /// Fraction of a full bar for `value`. `link_speed` is in bytes per second.
+    pub fn fraction(&mut self, value: f64, link_speed: Option<f64>) -> f64 {
+        self.fraction_at(value, link_speed, Instant::now())
+    }
+
+    fn fraction_at(&mut self, value: f64, link_speed: Option<f64>, now: Instant) -> f64 {
+        let min = self.fixed.min;
+        // a range without width, like a sensor configured with the same min and max, is full
+        // once the value reaches it
+        let step = |max: f64| if value >= max { 1. } else { 0. };
+        let linear = |max: f64| match max > min {
+            true => (value - min) / (max - min),
+            false => step(max),
+        };
+        match self.scaling {
+            Scaling::Fixed => linear(self.fixed.max),
+            Scaling::LinkSpeed => match link_speed {
+                Some(speed) => linear(speed),
+                None => linear(self.fixed.max),
+            },
+            Scaling::Peak { half_life, floor } => {
+                let decayed = self.peak.map_or(0., |(peak, time)| {
+                    peak * 0.5f64
+                        .powf(now.duration_since(time).as_secs_f64() / half_life.as_secs_f64())
+                });
+                let peak = decayed.max(value).max(floor);
+                self.peak = Some((peak, now));
+                linear(peak)
+            }
+            Scaling::Log => {
+                let log = |value: f64| (value - min).max(0.).ln_1p();
+                match self.fixed.max > min {
+                    true => log(value) / log(self.fixed.max),
+                    false => step(self.fixed.max),
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SCALE: Scale = Scale {
+        min: 20.,
+        max: 120.,
+    };
+
+    #[test]
+    fn scales_linearly() {
+        let mut scaler = Scaler::new(Scaling::Fixed, SCALE);
+        assert_eq!(scaler.fraction(20., None), 0.);
+        assert_eq!(scaler.fraction(70., Some(1000.)), 0.5);
+        assert_eq!(scaler.fraction(120., None), 1.);
+
+        let mut scaler = Scaler::new(Scaling::LinkSpeed, Scale::max(1000.));
+        assert_eq!(scaler.fraction(250., Some(500.)), 0.5);
+        assert_eq!(scaler.fraction(250., None), 0.25);
+    }
+
+    #[test]
+    fn scales_logarithmically() {
+        let mut scaler = Scaler::new(Scaling::Log, Scale::max(1e6));
+        assert_eq!(scaler.fraction(0., None), 0.);
+        assert_eq!(scaler.fraction(1e6, None), 1.);
+        let small = scaler.fraction(1e3, None);
+        assert!((0.49..0.51).contains(&small), "{small}");
+        assert_eq!(scaler.fraction(-5., None), 0.);
+    }
+
+    #[test]
+    fn fills_empty_ranges_at_their_value() {
+        let empty = Scale { min: 50., max: 50. };
+        for scaling in [Scaling::Fixed, Scaling::LinkSpeed, Scaling::Log] {
+            let mut scaler = Scaler::new(scaling, empty);
+            assert_eq!(scaler.fraction(49., None), 0., "{scaling:?}");
+            assert_eq!(scaler.fraction(50., None), 1., "{scaling:?}");
+        }
+        let mut scaler = Scaler::new(Scaling::Log, Scale { min: 50., max: 10. });
+        assert_eq!(scaler.fraction(20., None), 1.);
+        let mut scaler = Scaler::new(Scaling::LinkSpeed, Scale::max(100.));
+        assert_eq!(scaler.fraction(10., Some(0.)), 1.);
+    }
+
+    #[test]
+    fn decays_peak_towards_floor() {
+        let half_life = Duration::from_secs(10);
+        let mut scaler = Scaler::new(
+            Scaling::Peak {
+                half_life,
+                floor: 100.,
+            },
+            Scale::max(1.),
+        );
+        let start = Instant::now();
+        assert_eq!(scaler.fraction_at(50., None, start), 0.5);
+        assert_eq!(scaler.fraction_at(1000., None, start), 1.);
+        let fraction = scaler.fraction_at(250., None, start + half_life);
+        assert!((fraction - 0.5).abs() < 1e-9, "{fraction}");
+        let fraction = scaler.fraction_at(125., None, start + half_life * 2);
+        assert!((fraction - 0.5).abs() < 1e-9, "{fraction}");
+        // long after the peak, the floor is a full bar
+        assert_eq!(scaler.fraction_at(50., None, start + half_life * 20), 0.5);
+        assert_eq!(scaler.fraction_at(150., None, start + half_life * 20), 1.);
+    }
+}
@@ -0,0 +1,231 @@
+use std::{
+    collections::VecDeque,
+    f64::consts::PI,
+    io::{BufReader, Read as _},
+    process::{Child, Command, Stdio},
+    sync::{Arc, Mutex},
+    thread,
+};
+
+use anyhow::{Context as _, Result, bail};
+
+use super::{Collector, Sample, Unavailable};
+
+const RATE: u32 = 44100;
+/// Samples per FFT, about 23ms of audio
+const WINDOW: usize = 1024;
+const BANDS: usize = 16;
+const LOWEST_FREQUENCY: f64 = 50.;
+const HIGHEST_FREQUENCY: f64 = 16000.;
+/// Levels below this are shown as an empty band
+const FLOOR_DB: f64 = -60.;
+/// RMS level below which the output counts as silent
+const SILENCE: f64 = 0.001;
+
+/// Spectrum of what is currently playing, captured from the monitor of the default sink with
+/// `parec` and split into logarithmically spaced bands. The value is the overall level in
+/// percent. While nothing is playing, no bands are reported.
+pub struct Spectrum {
+    capture: Option<Capture>,
+}
+
+/// The running `parec` and the most recent samples it recorded
+struct Capture {
+    child: Child,
+    samples: Arc<Mutex<VecDeque<f64>>>,
+}
+
+impl Drop for Capture {
+    fn drop(&mut self) {
+        _ = self.child.kill();
+        _ = self.child.wait();
+    }
+}
+
+impl Spectrum {
+    pub fn new() -> Result<Self> {
+        let installed = Command::new("parec")
+            .arg("--version")
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status()
+            .is_ok_and(|status| status.success());
+        if !installed {
+            Err(Unavailable("parec is not installed"))?;
+        }
+        Ok(Self { capture: None })
+    }
+
+    fn spawn() -> Result<Capture> {
+        let mut child = Command::new("parec")
+            .args([
+                "--device=@DEFAULT_MONITOR@",
+                "--format=s16le",
+                &format!("--rate={RATE}"),
+                "--channels=1",
+                "--raw",
+                // keep the latency low instead of recording in large chunks
+                "--latency-msec=20",
+            ])
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .context("spawning parec")?;
+        let stdout = child.stdout.take().context("capturing stdout")?;
+        let samples = Arc::new(Mutex::new(VecDeque::from(vec![0.; WINDOW])));
+        let samples_clone = Arc::clone(&samples);
+        thread::spawn(move || {
+            let mut reader = BufReader::new(stdout);
+            let mut buf = [0; 2];
+            while reader.read_exact(&mut buf).is_ok() {
+                let mut samples = samples_clone.lock().unwrap();
+                samples.pop_front();
+                samples.push_back(i16::from_le_bytes(buf) as f64 / i16::MAX as f64);
+            }
+        });
+        Ok(Capture { child, samples })
+    }
+}
+
+impl Collector for Spectrum {
+    fn sample(&mut self) -> Result<Sample> {
+        if let Some(capture) = &mut self.capture
+            && let Some(status) = capture.child.try_wait().context("checking parec")?
+        {
+            self.capture = None;
+            bail!("parec exited with {status}, restarting");
+        }
+        let capture = match &mut self.capture {
+            Some(capture) => capture,
+            None => self.capture.insert(Self::spawn()?),
+        };
+        let samples = capture
+            .samples
+            .lock()
+            .unwrap()
+            .iter()
+            .copied()
+            .collect::<Vec<_>>();
+
+        let rms = (samples.iter().map(|s| s * s).sum::<f64>() / WINDOW as f64).sqrt();
+        if rms < SILENCE {
+            return Ok(0.0.into());
+        }
+        Ok(Sample {
+            value: ((20. * rms.log10() - FLOOR_DB) / -FLOOR_DB * 100.).max(0.),
+            parts: Some(bands(&samples)),
+            ..Sample::default()
+        })
+    }
+}
+
+/// Level of each band as a fraction between [`FLOOR_DB`] and full scale.
+fn bands(samples: &[f64]) -> Vec<f64> {
+    // Hann window against leakage between the bins
+    let mut re = samples
+        .iter()
+        .enumerate()
+        .map(|(i, s)| s * (0.5 - 0.5 * (2. * PI * i as f64 / (WINDOW - 1) as f64).cos()))
+        .collect::<Vec<_>>();
+    let mut im = vec![0.; WINDOW];
+    fft(&mut re, &mut im);
+
+    let bin_width = RATE as f64 / WINDOW as f64;
+    let ratio = (HIGHEST_FREQUENCY / LOWEST_FREQUENCY).powf(1. / BANDS as f64);
+    (0..BANDS)
+        .map(|band| {
+            let low = LOWEST_FREQUENCY * ratio.powi(band as i32);
+            let first = (low / bin_width).floor() as usize;
+            let last = ((low * ratio / bin_width).ceil() as usize).max(first + 1);
+            let peak = (first..last.min(WINDOW / 2))
+                .map(|bin| (re[bin] * re[bin] + im[bin] * im[bin]).sqrt())
+                .fold(0., f64::max);
+            // a full scale sine has a magnitude of a quarter of the window with a Hann window
+            let db = 20. * (peak / (WINDOW as f64 / 4.)).max(f64::MIN_POSITIVE).log10();
+            ((db - FLOOR_DB) / -FLOOR_DB).clamp(0., 1.)
+        })
+        .collect()
+}
+
+/// In-place iterative radix-2 FFT. The length must be a power of two.
+fn fft(re: &mut [f64], im: &mut [f64]) {
+    let n = re.len();
+    let mut j = 0;
+    for i in 1..n {
+        let mut bit = n >> 1;
+        while j & bit != 0 {
+            j ^= bit;
+            bit >>= 1;
+        }
+        j |= bit;
+        if i < j {
+            re.swap(i, j);
+            im.swap(i, j);
+        }
+    }
+    let mut len = 2;
+    while len <= n {
+        let angle = -2. * PI / len as f64;
+        for start in (0..n).step_by(len) {
+            for k in 0..len / 2 {
+                let (sin, cos) = (angle * k as f64).sin_cos();
+                let (a, b) = (start + k, start + k + len / 2);
+                let (br, bi) = (re[b] * cos - im[b] * sin, re[b] * sin + im[b] * cos);
+                re[b] = re[a] - br;
+                im[b] = im[a] - bi;
+                re[a] += br;
+                im[a] += bi;
+            }
+        }
+        len <<= 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sine(frequency: f64, amplitude: f64) -> Vec<f64> {
+        (0..WINDOW)
+            .map(|i| amplitude * (2. * PI * frequency * i as f64 / RATE as f64).sin())
+            .collect()
+    }
+
+    #[test]
+    fn transforms_sine_into_its_bin() {
+        // exactly bin 32, so there is no leakage without a window
+        let mut re = sine(32. * RATE as f64 / WINDOW as f64, 1.);
+        let mut im = vec![0.; WINDOW];
+        fft(&mut re, &mut im);
+        let magnitudes = re
+            .iter()
+            .zip(&im)
+            .map(|(re, im)| (re * re + im * im).sqrt())
+            .collect::<Vec<_>>();
+        assert!((magnitudes[32] - WINDOW as f64 / 2.).abs() < 1e-6);
+        assert!(
+            magnitudes[..WINDOW / 2]
+                .iter()
+                .enumerate()
+                .all(|(bin, magnitude)| bin == 32 || *magnitude < 1e-6)
+        );
+    }
+
+    #[test]
+    fn puts_sine_into_its_band() {
+        let ratio = (HIGHEST_FREQUENCY / LOWEST_FREQUENCY).powf(1. / BANDS as f64);
+        for band in [4, 8, 12] {
+            // the geometric center of the band
+            let frequency = LOWEST_FREQUENCY * ratio.powf(band as f64 + 0.5);
+            let levels = bands(&sine(frequency, 0.5));
+            assert_eq!(levels.len(), BANDS);
+            let loudest = (0..BANDS)
+                .max_by(|a, b| levels[*a].total_cmp(&levels[*b]))
+                .unwrap();
+            assert_eq!(loudest, band, "{frequency} Hz: {levels:?}");
+            // half of full scale is about -6 dB
+            assert!((0.85..=0.95).contains(&levels[band]), "{levels:?}");
+        }
+    }
+}
@@ -0,0 +1,194 @@
+use std::{
+    path::PathBuf,
+    process::{Command, Stdio},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::{Context as _, Result, bail};
+
+use super::{Collector, Sample, Scale, Unavailable, Unit};
+
+/// Fails with [`Unavailable`] if the systemd tool `program` isn't installed.
+fn installed(program: &str) -> Result<()> {
+    let installed = Command::new(program)
+        .arg("--version")
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .is_ok_and(|status| status.success());
+    if !installed {
+        Err(Unavailable("systemd is not installed"))?;
+    }
+    Ok(())
+}
+
+/// Runs a systemd tool and returns what it printed.
+fn run(command: &mut Command) -> Result<String> {
+    let output = command
+        .stdin(Stdio::null())
+        .stderr(Stdio::piped())
+        .output()
+        .with_context(|| format!("running {:?}", command.get_program()))?;
+    if !output.status.success() {
+        bail!(
+            "{:?} exited with {}: {}",
+            command.get_program(),
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Number of failed systemd units. Their names are sent along as text.
+pub struct FailedUnits {
+    user: bool,
+    max: f64,
+}
+
+impl FailedUnits {
+    pub fn new(user: bool, max: f64) -> Result<Self> {
+        installed("systemctl")?;
+        Ok(Self { user, max })
+    }
+}
+
+impl Collector for FailedUnits {
+    fn unit(&self) -> Unit {
+        Unit::None
+    }
+
+    fn scale(&self) -> Scale {
+        Scale::max(self.max)
+    }
+
+    fn sample(&mut self) -> Result<Sample> {
+        let mut command = Command::new("systemctl");
+        if self.user {
+            command.arg("--user");
+        }
+        let output = run(command.args([
+            "list-units",
+            "--state=failed",
+            "--plain",
+            "--no-legend",
+            "--no-pager",
+        ]))?;
+        let units = failed_units(&output);
+        Ok(Sample {
+            value: units.len() as f64,
+            text: (!units.is_empty()).then(|| units.join(", ")),
+            ..Sample::default()
+        })
+    }
+}
+
+/// Names of the units listed by `systemctl list-units --plain --no-legend`.
+fn failed_units(output: &str) -> Vec<&str> {
+    // lines like `foo.service loaded failed failed Foo`
+    output
+        .lines()
+        .filter_map(|line| line.split_whitespace().next())
+        .collect()
+}
+
+/// Journal entries with priority `err` or higher per minute, counted over the last `window`.
+pub struct JournalErrors {
+    user: bool,
+    /// Journal file or directory to read instead of the local journal
+    journal: Option<PathBuf>,
+    window: Duration,
+    max: f64,
+}
+
+impl JournalErrors {
+    pub fn new(user: bool, journal: Option<PathBuf>, window: Duration, max: f64) -> Result<Self> {
+        installed("journalctl")?;
+        Ok(Self {
+            user,
+            journal,
+            window,
+            max,
+        })
+    }
+}
+
+impl Collector for JournalErrors {
+    fn unit(&self) -> Unit {
+        Unit::None
+    }
+
+    fn scale(&self) -> Scale {
+        Scale::max(self.max)
+    }
+
+    fn sample(&mut self) -> Result<Sample> {
+        let since = (SystemTime::now() - self.window)
+            .duration_since(UNIX_EPOCH)
+            .context("system time is before 1970")?
+            .as_secs();
+        let mut command = Command::new("journalctl");
+        match &self.journal {
+            Some(journal) if journal.is_dir() => {
+                command.arg("--directory").arg(journal);
+            }
+            Some(journal) => {
+                command.arg("--file").arg(journal);
+            }
+            None if self.user => {
+                command.arg("--user");
+            }
+            None => {}
+        }
+        let output = run(command
+            .args(["--priority=err", "--quiet", "--no-pager", "--output=json"])
+            .args(["--output-fields=PRIORITY", &format!("--since=@{since}")]))?;
+        let minutes = self.window.as_secs_f64() / 60.;
+        Ok((errors(&output) as f64 / minutes).into())
+    }
+}
+
+/// Number of entries with priority `err` or higher in the output of `journalctl --output=json`,
+/// which puts every entry on one line, even multi-line messages.
+fn errors(output: &str) -> usize {
+    output
+        .lines()
+        .filter_map(|line| serde_json::from_str::<serde_json::Value>(line).ok())
+        .filter(|entry| {
+            entry["PRIORITY"]
+                .as_str()
+                .and_then(|priority| priority.parse::<u8>().ok())
+                .is_some_and(|priority| priority <= 3)
+        })
+        .count()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `journalctl --output=json` with one entry of every priority from `crit` to `info`
+    const JOURNAL: &str = include_str!("../../fixtures/journal.json");
+
+    #[test]
+    fn parses_failed_units() {
+        let output = "foo.service loaded failed failed Foo Daemon\n\
+                      bar.mount   loaded failed failed /bar\n";
+        assert_eq!(failed_units(output), ["foo.service", "bar.mount"]);
+        assert!(failed_units("").is_empty());
+    }
+
+    #[test]
+    fn counts_journal_errors() {
+        assert_eq!(errors(JOURNAL), 2);
+        assert_eq!(errors(""), 0);
+        assert_eq!(errors("-- No entries --\n"), 0);
+    }
+
+    #[test]
+    #[ignore = "reads the journal of the machine running the tests"]
+    fn reads_local_journal() {
+        let mut errors = JournalErrors::new(false, None, Duration::from_secs(3600), 10.).unwrap();
+        assert!(errors.sample().unwrap().value >= 0.);
+    }
+}
@@ -0,0 +1,60 @@
+use anyhow::{Context as _, Result};
+use sysinfo::{MemoryRefreshKind, ProcessRefreshKind, ProcessesToUpdate, RefreshKind, System};
+
+use super::{Collector, Sample};
+
+#[derive(Debug, Clone, Copy, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum By {
+    /// Usage in percent of one core, like `top`
+    Cpu,
+    /// Used memory in percent of the RAM
+    Memory,
+}
+
+/// The process using the most CPU or memory. Its name is sent along as text.
+pub struct TopProcess {
+    sys: System,
+    by: By,
+}
+
+impl TopProcess {
+    pub fn new(by: By) -> Self {
+        Self {
+            sys: System::new_with_specifics(
+                RefreshKind::nothing().with_memory(MemoryRefreshKind::nothing().with_ram()),
+            ),
+            by,
+        }
+    }
+}
+
+impl Collector for TopProcess {
+    fn sample(&mut self) -> Result<Sample> {
+        let refresh = match self.by {
+            By::Cpu => ProcessRefreshKind::nothing().with_cpu(),
+            By::Memory => ProcessRefreshKind::nothing().with_memory(),
+        };
+        self.sys
+            .refresh_processes_specifics(ProcessesToUpdate::All, true, refresh);
+        let total_memory = self.sys.total_memory() as f64;
+        let (process, value) = self
+            .sys
+            .processes()
+            .values()
+            .map(|process| {
+                let value = match self.by {
+                    By::Cpu => process.cpu_usage() as f64,
+                    By::Memory => process.memory() as f64 / total_memory * 100.,
+                };
+                (process, value)
+            })
+            .max_by(|(_, a), (_, b)| a.total_cmp(b))
+            .context("no processes found")?;
+        Ok(Sample {
+            value,
+            text: Some(process.name().to_string_lossy().into_owned()),
+            ..Sample::default()
+        })
+    }
+}
@@ -0,0 +1,222 @@
+use std::{fs, path::PathBuf, time::Duration};
+
+use anyhow::{Context as _, Result};
+use log::warn;
+
+use crate::collectors::{self, CollectorConfig, CollectorKind, gpu::Aggregate, network::Direction};
+
+/// Number of samples the server keeps, so more buffered samples would be dropped anyway
+pub const MAX_BUFFER_SIZE: usize = 6000;
+
+#[derive(Debug, serde::Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// Base URL of the server, discovered via mDNS if not set
+    pub server_addr: Option<String>,
+    /// Instance name of the server to prefer when discovering via mDNS
+    pub server_name: Option<String>,
+    /// Number of samples to keep while the server is unreachable, sent once it is back. At most
+    /// [`MAX_BUFFER_SIZE`].
+    pub buffer_size: usize,
+    /// Time between two snapshots sent to the server
+    #[serde(with = "humantime_serde")]
+    pub send_interval: Duration,
+    /// Metrics to collect, in the order they are displayed
+    pub collectors: Vec<CollectorConfig>,
+    pub tls: TlsConfig,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            server_addr: None,
+            server_name: None,
+            buffer_size: 0,
+            send_interval: Duration::from_millis(100),
+            collectors: default_collectors(),
+            tls: TlsConfig::default(),
+        }
+    }
+}
+
+fn default_collectors() -> Vec<CollectorConfig> {
+    let network = |direction| CollectorKind::Network {
+        interface: Default::default(),
+        direction,
+        max: collectors::default_network_max(),
+    };
+    [
+        CollectorKind::Volume {
+            backend: Default::default(),
+            device: None,
+        },
+        CollectorKind::Memory {},
+        CollectorKind::Cpu {
+            aggregate: Default::default(),
+        },
+        CollectorKind::Gpu {
+            card: None,
+            aggregate: Aggregate::Single,
+        },
+        CollectorKind::Vram {
+            card: None,
+            aggregate: Aggregate::Single,
+        },
+        network(Direction::Up),
+        network(Direction::Down),
+    ]
+    .into_iter()
+    .map(CollectorConfig::from)
+    .collect()
+}
+
+#[derive(Debug, Default, serde::Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TlsConfig {
+    /// PEM file with the only certificate to trust as root, instead of the system CAs
+    pub ca_cert: Option<PathBuf>,
+    /// SHA-256 fingerprint of the server certificate as hex, optionally colon separated. When
+    /// set, only exactly this certificate is accepted and no CA or hostname checks are done.
+    pub fingerprint: Option<String>,
+}
+
+impl Config {
+    /// Loads the config from the path given as the first command line argument, or uses the
+    /// defaults if there is none.
+    pub fn load() -> Result<Self> {
+        let Some(path) = std::env::args_os().nth(1) else {
+            return Ok(Self::default());
+        };
+        let src = fs::read_to_string(&path)
+            .with_context(|| format!("reading config file {}", path.display()))?;
+        let mut config: Self = toml::from_str(&src).context("parsing config file")?;
+        if config.buffer_size > MAX_BUFFER_SIZE {
+            warn!("buffer_size is limited to {MAX_BUFFER_SIZE}, the size of the server's history");
+            config.buffer_size = MAX_BUFFER_SIZE;
+        }
+        Ok(config)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_collectors() {
+        let config: Config = toml::from_str(
+            r#"
+            [[collectors]]
+            type = "memory"
+            name = "ram"
+            interval = "1s"
+
+            [[collectors]]
+            type = "network"
+            interface = ["enp*", "wl*"]
+            direction = "down"
+            scale = { mode = "log" }
+            "#,
+        )
+        .unwrap();
+        assert_eq!(config.collectors.len(), 2);
+        assert_eq!(config.collectors[0].name.as_deref(), Some("ram"));
+        assert_eq!(
+            config.collectors[0].schedule.interval,
+            Duration::from_secs(1)
+        );
+        assert!(matches!(
+            config.collectors[1].kind,
+            CollectorKind::Network {
+                direction: Direction::Down,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn defaults_schedule_per_collector() {
+        let config: Config = toml::from_str(
+            r#"
+            [[collectors]]
+            type = "memory"
+
+            [[collectors]]
+            type = "media"
+
+            [[collectors]]
+            type = "media"
+            interval = "200ms"
+
+            [[collectors]]
+            type = "top_process"
+            by = "cpu"
+
+            [[collectors]]
+            type = "filesystem"
+            mount_point = "/"
+            "#,
+        )
+        .unwrap();
+        let schedules: Vec<_> = config
+            .collectors
+            .iter()
+            .map(|collector| (collector.schedule.interval, collector.schedule.timeout))
+            .collect();
+        assert_eq!(
+            schedules,
+            [
+                (Duration::from_millis(100), Duration::from_secs(1)),
+                (Duration::from_secs(1), Duration::from_secs(3)),
+                (Duration::from_millis(200), Duration::from_secs(1)),
+                (Duration::from_secs(1), Duration::from_secs(3)),
+                (Duration::from_secs(10), Duration::from_secs(30)),
+            ]
+        );
+    }
+
+    #[test]
+    fn names_metrics_by_collector() {
+        let config: Config = toml::from_str(
+            r#"
+            [[collectors]]
+            type = "hwmon"
+            chip = "k10temp"
+            sensor = "temp"
+
+            [[collectors]]
+            type = "battery"
+            reading = "time"
+
+            [[collectors]]
+            type = "cgroup"
+            resource = "memory"
+            "#,
+        )
+        .unwrap();
+        let names: Vec<_> = config
+            .collectors
+            .iter()
+            .map(|collector| collector.kind.default_name())
+            .collect();
+        assert_eq!(names, ["temp", "battery_time", "cgroup_mem"]);
+    }
+
+    #[test]
+    fn rejects_unknown_collector_fields() {
+        for collector in [
+            r#"type = "cpu"
+               intervall = "1s""#,
+            r#"type = "volume"
+               devcie = "foo""#,
+            r#"type = "memory"
+               foo = 1"#,
+        ] {
+            let config = format!("[[collectors]]\n{collector}");
+            assert!(
+                toml::from_str::<Config>(&config).is_err(),
+                "accepted {config:?}"
+            );
+        }
+    }
+}
@@ -0,0 +1,208 @@
+use std::{
+    collections::VecDeque,
+    time::{Duration, Instant},
+};
+
+use anyhow::{Context as _, Result};
+use log::{debug, error, info, warn};
+use reqwest::Client;
+use tokio::task;
+
+use crate::{Context, config::Config, mdns, tls};
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+const INITIAL_BACKOFF: Duration = Duration::from_millis(500);
+const MAX_BACKOFF: Duration = Duration::from_secs(30);
+/// Minimum time between two log messages about the server still being unreachable
+const LOG_INTERVAL: Duration = Duration::from_secs(60);
+/// Buffered samples sent per request, which keeps requests well below the server's 4 MiB limit
+/// with samples of about a kilobyte
+const BACKFILL_CHUNK: usize = 500;
+
+enum State {
+    Connected,
+    Disconnected {
+        since: Instant,
+        retry_at: Instant,
+        backoff: Duration,
+        attempts: u32,
+        last_log: Instant,
+    },
+}
+
+/// Sends samples to the server, retrying with exponential backoff while it is unreachable and
+/// replaying the last few samples once it is back.
+pub struct Connection {
+    client: Client,
+    configured_addr: Option<String>,
+    server_name: Option<String>,
+    addr: Option<String>,
+    state: State,
+    buffer: VecDeque<Context>,
+    buffer_size: usize,
+}
+
+impl Connection {
+    pub fn new(config: &Config) -> Result<Self> {
+        Ok(Self {
+            client: tls::configure(Client::builder().timeout(REQUEST_TIMEOUT), &config.tls)?
+                .build()
+                .context("creating HTTP client")?,
+            configured_addr: config.server_addr.clone(),
+            server_name: config.server_name.clone(),
+            addr: config.server_addr.clone(),
+            state: State::Connected,
+            buffer: VecDeque::with_capacity(config.buffer_size),
+            buffer_size: config.buffer_size,
+        })
+    }
+
+    pub async fn send(&mut self, ctx: Context) {
+        if let State::Disconnected { retry_at, .. } = self.state
+            && Instant::now() < retry_at
+        {
+            self.buffer(ctx);
+            return;
+        }
+
+        match self.try_send(&ctx).await {
+            Ok(()) => {
+                if let State::Disconnected { since, .. } = self.state {
+                    info!("reconnected to server after {:.0?}", since.elapsed());
+                }
+                self.state = State::Connected;
+            }
+            Err(err) => {
+                self.buffer(ctx);
+                self.on_failure(err);
+            }
+        }
+    }
+
+    pub async fn reset_state(&mut self) -> Result<()> {
+        let addr = self.addr.as_ref().context("no server address known")?;
+        self.client
+            .post(format!("{addr}/reset-state"))
+            .send()
+            .await
+            .and_then(|res| res.error_for_status())
+            .context("resetting state")?;
+        Ok(())
+    }
+
+    /// Sends the current sample, then replays the buffer. A failing replay doesn't fail the
+    /// live sample.
+    async fn try_send(&mut self, ctx: &Context) -> Result<()> {
+        let addr = match &self.addr {
+            Some(addr) => addr,
+            None => {
+                let name = self.server_name.clone();
+                let addr = task::spawn_blocking(move || mdns::discover(name.as_deref()))
+                    .await
+                    .context("discovering server")??;
+                info!("discovered server at {addr}");
+                self.addr.insert(addr)
+            }
+        }
+        .clone();
+
+        self.client
+            .post(format!("{addr}/state"))
+            .json(ctx)
+            .send()
+            .await
+            .and_then(|res| res.error_for_status())
+            .context("sending state")?;
+
+        if !self.buffer.is_empty() {
+            self.replay(&addr).await;
+        }
+        Ok(())
+    }
+
+    /// Sends the buffered samples in chunks. Samples the server rejects, e.g. because it doesn't
+    /// support backfilling, are dropped, as sending them again would fail the same way.
+    async fn replay(&mut self, addr: &str) {
+        debug!("replaying {} buffered samples", self.buffer.len());
+        while !self.buffer.is_empty() {
+            let chunk = self.buffer.len().min(BACKFILL_CHUNK);
+            let samples = self.buffer.range(..chunk).collect::<Vec<_>>();
+            let res = self
+                .client
+                .post(format!("{addr}/backfill"))
+                .json(&samples)
+                .send()
+                .await;
+            match res {
+                Ok(res) if res.status().is_success() => {
+                    self.buffer.drain(..chunk);
+                }
+                Ok(res) if res.status().is_client_error() => {
+                    warn!(
+                        "server rejected buffered samples with {}, dropping {} of them",
+                        res.status(),
+                        self.buffer.len()
+                    );
+                    self.buffer.clear();
+                }
+                res => {
+                    let err = res.and_then(|res| res.error_for_status()).err();
+                    debug!("replaying buffered samples again later: {err:?}");
+                    return;
+                }
+            }
+        }
+    }
+
+    fn buffer(&mut self, ctx: Context) {
+        if self.buffer_size == 0 {
+            return;
+        }
+        if self.buffer.len() == self.buffer_size {
+            self.buffer.pop_front();
+        }
+        self.buffer.push_back(ctx);
+    }
+
+    fn on_failure(&mut self, err: anyhow::Error) {
+        // the server may have moved, look for it again
+        if self.configured_addr.is_none() {
+            self.addr = None;
+        }
+
+        let now = Instant::now();
+        match &mut self.state {
+            State::Connected => {
+                error!("cannot reach server: {err:?}");
+                self.state = State::Disconnected {
+                    since: now,
+                    retry_at: now + INITIAL_BACKOFF,
+                    backoff: INITIAL_BACKOFF,
+                    attempts: 1,
+                    last_log: now,
+                };
+            }
+            State::Disconnected {
+                since,
+                retry_at,
+                backoff,
+                attempts,
+                last_log,
+            } => {
+                *attempts += 1;
+                *backoff = (*backoff * 2).min(MAX_BACKOFF);
+                *retry_at = now + *backoff;
+                match now.duration_since(*last_log) >= LOG_INTERVAL {
+                    true => {
+                        warn!(
+                            "server still unreachable after {attempts} attempts in {:.0?}: {err:#}",
+                            since.elapsed()
+                        );
+                        *last_log = now;
+                    }
+                    false => debug!("retrying in {backoff:?}: {err:#}"),
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,46 @@
+/// Whether `name` matches a pattern where `*` matches any number of characters and `?` matches
+/// one.
+pub fn matches(pattern: &str, name: &str) -> bool {
+    matches_bytes(pattern.as_bytes(), name.as_bytes())
+}
+
+fn matches_bytes(pattern: &[u8], name: &[u8]) -> bool {
+    match (pattern.split_first(), name.split_first()) {
+        (None, None) => true,
+        (Some((b'*', rest)), _) => {
+            matches_bytes(rest, name)
+                || name
+                    .split_first()
+                    .is_some_and(|(_, name)| matches_bytes(pattern, name))
+        }
+        (Some((b'?', rest)), Some((_, name))) => matches_bytes(rest, name),
+        (Some((p, rest)), Some((n, name))) => p == n && matches_bytes(rest, name),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_wildcards() {
+        assert!(matches("wl*", "wlan0"));
+        assert!(matches("wl*", "wl"));
+        assert!(!matches("wl*", "enp3s0"));
+        assert!(matches("*.scope", "docker-1234.scope"));
+        assert!(matches("docker-*.scope", "docker-1234.scope"));
+        assert!(!matches("docker-*.scope", "docker-1234.service"));
+        assert!(matches("eth?", "eth0"));
+        assert!(!matches("eth?", "eth10"));
+        assert!(matches("*", ""));
+    }
+
+    #[test]
+    fn matches_names_exactly() {
+        assert!(matches("enp3s0", "enp3s0"));
+        assert!(!matches("enp3s0", "enp3s01"));
+        assert!(!matches("enp3s0", "enp3s"));
+        assert!(!matches("", "lo"));
+    }
+}
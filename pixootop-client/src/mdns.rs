@@ -0,0 +1,62 @@
+use std::{
+    net::IpAddr,
+    time::{Duration, Instant},
+};
+
+use anyhow::{Context as _, Result};
+use log::{debug, warn};
+use mdns_sd::{ServiceDaemon, ServiceEvent, ServiceInfo};
+
+const SERVICE_TYPE: &str = "_pixootop._tcp.local.";
+const DISCOVERY_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// Looks for servers advertised via DNS-SD and returns the base URL of the one called
+/// `instance_name`, or of the first one found if there is no such instance.
+pub fn discover(instance_name: Option<&str>) -> Result<String> {
+    let mdns = ServiceDaemon::new().context("starting mDNS daemon")?;
+    let events = mdns
+        .browse(SERVICE_TYPE)
+        .context("browsing mDNS services")?;
+
+    let deadline = Instant::now() + DISCOVERY_TIMEOUT;
+    let mut first = None;
+    let mut found = None;
+    while let Ok(event) = events.recv_deadline(deadline) {
+        let ServiceEvent::ServiceResolved(info) = event else {
+            continue;
+        };
+        let name = info
+            .get_fullname()
+            .trim_end_matches(SERVICE_TYPE)
+            .trim_end_matches('.');
+        let Some(url) = service_url(&info) else {
+            continue;
+        };
+        debug!("found server {name:?} at {url}");
+        if instance_name.is_none_or(|n| n == name) {
+            found = Some(url);
+            break;
+        }
+        first.get_or_insert(url);
+    }
+    _ = mdns.shutdown();
+
+    if found.is_none() && first.is_some() {
+        warn!("no server called {instance_name:?} found, using another one");
+    }
+    found.or(first).context("no server found via mDNS")
+}
+
+fn service_url(info: &ServiceInfo) -> Option<String> {
+    let scheme = info.get_property_val_str("scheme").unwrap_or("http");
+    let addrs = info.get_addresses();
+    // prefer IPv4, link-local IPv6 addresses would need a scope ID
+    let addr = addrs
+        .iter()
+        .find(|addr| addr.is_ipv4())
+        .or_else(|| addrs.iter().next())?;
+    Some(match addr {
+        IpAddr::V4(addr) => format!("{scheme}://{addr}:{}", info.get_port()),
+        IpAddr::V6(addr) => format!("{scheme}://[{addr}]:{}", info.get_port()),
+    })
+}
@@ -0,0 +1,200 @@
+use std::{
+    thread,
+    time::{Duration, Instant},
+};
+
+use chrono::Local;
+use log::{debug, error, info, warn};
+use tokio::sync::watch;
+
+use crate::{
+    Context, Metric, PROGRESS_RANGE,
+    collectors::{CollectorConfig, Color, Sample, Unavailable, Unit, scaling::Scaler},
+};
+
+/// Longest time between two attempts to build a collector
+const MAX_BUILD_BACKOFF: Duration = Duration::from_secs(60);
+
+enum State {
+    /// Not built yet, or failed to build
+    Missing,
+    /// The hardware for this collector doesn't exist, so it is left out
+    Unavailable,
+    Ready(Status),
+}
+
+struct Status {
+    name: String,
+    unit: Unit,
+    /// Latest sample, its progress, and when it was taken
+    latest: Option<(Sample, u8, Instant)>,
+}
+
+/// Handle to a collector running on its own thread.
+struct Running {
+    name: String,
+    color: Option<Color>,
+    timeout: Duration,
+    rx: watch::Receiver<State>,
+}
+
+/// Runs all configured collectors and combines their latest samples.
+pub struct Sampler {
+    collectors: Vec<Running>,
+}
+
+impl Sampler {
+    pub fn spawn(configs: &[CollectorConfig]) -> Self {
+        Self {
+            collectors: configs
+                .iter()
+                .cloned()
+                .flat_map(CollectorConfig::expand)
+                .map(spawn)
+                .collect(),
+        }
+    }
+
+    pub fn snapshot(&self) -> Context {
+        Context {
+            metrics: self
+                .collectors
+                .iter()
+                .filter_map(|collector| {
+                    let state = collector.rx.borrow();
+                    let status = match &*state {
+                        State::Ready(status) => status,
+                        State::Unavailable => return None,
+                        State::Missing => {
+                            return Some(Metric {
+                                name: collector.name.clone(),
+                                color: collector.color,
+                                ..Metric::default()
+                            });
+                        }
+                    };
+                    let latest = status
+                        .latest
+                        .as_ref()
+                        .filter(|(_, _, time)| time.elapsed() <= collector.timeout);
+                    Some(Metric {
+                        name: status.name.clone(),
+                        color: collector.color,
+                        unit: Some(status.unit),
+                        value: latest.map(|(sample, _, _)| sample.value),
+                        progress: latest.map(|(_, progress, _)| *progress),
+                        muted: latest.and_then(|(sample, _, _)| sample.muted),
+                        charging: latest.and_then(|(sample, _, _)| sample.charging),
+                        playing: latest.and_then(|(sample, _, _)| sample.playing),
+                        text: latest.and_then(|(sample, _, _)| sample.text.clone()),
+                        parts: latest.and_then(|(sample, _, _)| {
+                            Some(
+                                sample
+                                    .parts
+                                    .as_ref()?
+                                    .iter()
+                                    .copied()
+                                    .map(progress)
+                                    .collect(),
+                            )
+                        }),
+                    })
+                })
+                .collect(),
+            time: Local::now(),
+        }
+    }
+}
+
+fn progress(fraction: f64) -> u8 {
+    (fraction * PROGRESS_RANGE)
+        .round()
+        .clamp(0., u8::MAX as f64) as u8
+}
+
+/// Spawns a thread which builds the collector and samples it every `schedule.interval`. A
+/// collector which fails to build stays missing and is built again with backoff, unless it is
+/// [`Unavailable`].
+fn spawn(config: CollectorConfig) -> Running {
+    let name = config
+        .name
+        .clone()
+        .unwrap_or_else(|| config.kind.default_name().to_string());
+    let color = config.color;
+    let (tx, rx) = watch::channel(State::Missing);
+    let thread_name = name.clone();
+    thread::spawn(move || {
+        let name = thread_name;
+        // what a collector needs may not be up yet, like pulse right after login
+        let mut backoff = config.schedule.interval;
+        let mut collector = loop {
+            match config.kind.build(&config.schedule) {
+                Ok(collector) => break collector,
+                Err(err) if err.is::<Unavailable>() => {
+                    warn!("leaving out {name} collector: {err:#}");
+                    tx.send_replace(State::Unavailable);
+                    return;
+                }
+                Err(err) => {
+                    match backoff == config.schedule.interval {
+                        true => error!("failed to initialize {name} collector, retrying: {err:?}"),
+                        false => debug!("initializing {name} collector failed: {err:#}"),
+                    }
+                    thread::sleep(backoff);
+                    backoff = (backoff * 2).min(MAX_BUILD_BACKOFF);
+                    if tx.is_closed() {
+                        return;
+                    }
+                }
+            }
+        };
+        debug!("initialized {name} collector");
+        tx.send_replace(State::Ready(Status {
+            name: name.clone(),
+            unit: collector.unit(),
+            latest: None,
+        }));
+        let mut scaler = Scaler::new(config.scale, collector.scale());
+
+        let mut failing = false;
+        let mut next = Instant::now();
+        while !tx.is_closed() {
+            let latest = match collector.sample() {
+                Ok(sample) => {
+                    if failing {
+                        info!("{name} collector recovered");
+                        failing = false;
+                    }
+                    let fraction = scaler.fraction(sample.value, collector.link_speed());
+                    Some((sample, progress(fraction), Instant::now()))
+                }
+                Err(err) => {
+                    match failing {
+                        true => debug!("sampling {name} failed: {err:#}"),
+                        false => error!("sampling {name} failed: {err:?}"),
+                    }
+                    failing = true;
+                    None
+                }
+            };
+            tx.send_modify(|state| {
+                if let State::Ready(status) = state {
+                    status.latest = latest;
+                }
+            });
+
+            next += config.schedule.interval;
+            let now = Instant::now();
+            match next.checked_duration_since(now) {
+                Some(wait) => thread::sleep(wait),
+                None => next = now,
+            }
+        }
+    });
+    Running {
+        name,
+        color,
+        timeout: config.schedule.timeout,
+        rx,
+    }
+}
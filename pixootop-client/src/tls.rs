@@ -0,0 +1,141 @@
+use std::{fs, sync::Arc};
+
+use anyhow::{Context as _, Result, bail};
+use reqwest::{Certificate, ClientBuilder};
+use rustls::{
+    ClientConfig, DigitallySignedStruct, Error, SignatureScheme,
+    client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier},
+    crypto::{CryptoProvider, verify_tls12_signature, verify_tls13_signature},
+    pki_types::{CertificateDer, ServerName, UnixTime},
+};
+
+use crate::config::TlsConfig;
+
+pub fn configure(mut builder: ClientBuilder, config: &TlsConfig) -> Result<ClientBuilder> {
+    if let Some(fingerprint) = &config.fingerprint {
+        let verifier = PinnedCertVerifier {
+            fingerprint: parse_fingerprint(fingerprint)?,
+            provider: Arc::new(rustls::crypto::ring::default_provider()),
+        };
+        let tls = ClientConfig::builder_with_provider(Arc::clone(&verifier.provider))
+            .with_safe_default_protocol_versions()
+            .context("configuring TLS protocol versions")?
+            .dangerous()
+            .with_custom_certificate_verifier(Arc::new(verifier))
+            .with_no_client_auth();
+        builder = builder.use_preconfigured_tls(tls);
+    } else if let Some(path) = &config.ca_cert {
+        let pem = fs::read(path).context("reading CA certificate")?;
+        builder = builder
+            .use_rustls_tls()
+            .tls_built_in_root_certs(false)
+            .add_root_certificate(Certificate::from_pem(&pem).context("parsing CA certificate")?);
+    }
+    Ok(builder)
+}
+
+fn parse_fingerprint(str: &str) -> Result<[u8; 32]> {
+    let hex = str.replace(':', "");
+    if hex.len() != 64 {
+        bail!("certificate fingerprint must be a SHA-256 hash");
+    }
+    // `from_str_radix` would also accept a leading `+`
+    if !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+        bail!("certificate fingerprint must be hexadecimal");
+    }
+    let mut fingerprint = [0; 32];
+    for (i, byte) in fingerprint.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(&hex[i * 2..i * 2 + 2], 16)?;
+    }
+    Ok(fingerprint)
+}
+
+/// Accepts exactly one server certificate, identified by its SHA-256 hash.
+#[derive(Debug)]
+struct PinnedCertVerifier {
+    fingerprint: [u8; 32],
+    provider: Arc<CryptoProvider>,
+}
+
+impl ServerCertVerifier for PinnedCertVerifier {
+    fn verify_server_cert(
+        &self,
+        end_entity: &CertificateDer<'_>,
+        _intermediates: &[CertificateDer<'_>],
+        _server_name: &ServerName<'_>,
+        _ocsp_response: &[u8],
+        _now: UnixTime,
+    ) -> Result<ServerCertVerified, Error> {
+        match ring::digest::digest(&ring::digest::SHA256, end_entity).as_ref() == self.fingerprint {
+            true => Ok(ServerCertVerified::assertion()),
+            false => Err(Error::General(
+                "server certificate does not match pinned fingerprint".to_string(),
+            )),
+        }
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, Error> {
+        verify_tls12_signature(
+            message,
+            cert,
+            dss,
+            &self.provider.signature_verification_algorithms,
+        )
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, Error> {
+        verify_tls13_signature(
+            message,
+            cert,
+            dss,
+            &self.provider.signature_verification_algorithms,
+        )
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.provider
+            .signature_verification_algorithms
+            .supported_schemes()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FINGERPRINT: &str = "00:11:22:33:44:55:66:77:88:99:AA:BB:CC:DD:EE:FF:\
+                               00:11:22:33:44:55:66:77:88:99:aa:bb:cc:dd:ee:ff";
+
+    #[test]
+    fn parses_fingerprint_with_and_without_colons() {
+        let expected: [u8; 32] = std::array::from_fn(|i| (i as u8 % 16) * 0x11);
+        assert_eq!(parse_fingerprint(FINGERPRINT).unwrap(), expected);
+        assert_eq!(
+            parse_fingerprint(&FINGERPRINT.replace(':', "")).unwrap(),
+            expected
+        );
+    }
+
+    #[test]
+    fn rejects_wrong_length() {
+        assert!(parse_fingerprint("00:11:22").is_err());
+        assert!(parse_fingerprint(&format!("{FINGERPRINT}:00")).is_err());
+    }
+
+    #[test]
+    fn rejects_non_hex_characters() {
+        assert!(parse_fingerprint(&FINGERPRINT.replacen("00", "+0", 1)).is_err());
+        assert!(parse_fingerprint(&FINGERPRINT.replacen("00", "0g", 1)).is_err());
+        assert!(parse_fingerprint(&FINGERPRINT.replacen("00", "ä", 1)).is_err());
+    }
+}
@@ -0,0 +1,120 @@
+use std::{fs, path::PathBuf};
+
+use anyhow::{Context as _, Result};
+
+use crate::{
+    popup::PopupConfig,
+    widgets::{self, Widget},
+};
+
+#[derive(Debug, serde::Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub pixoo_mac_addr: String,
+    pub bind_addr: String,
+    pub port: u16,
+    pub tls: Option<TlsConfig>,
+    pub mdns: MdnsConfig,
+    /// What to draw, in order
+    pub widgets: Vec<Widget>,
+    pub popup: PopupConfig,
+    pub privacy: PrivacyConfig,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            pixoo_mac_addr: "11:75:58:35:2B:35".to_string(),
+            bind_addr: "0.0.0.0".to_string(),
+            port: 6969,
+            tls: None,
+            mdns: MdnsConfig::default(),
+            widgets: widgets::default_widgets(),
+            popup: PopupConfig::default(),
+            privacy: PrivacyConfig::default(),
+        }
+    }
+}
+
+#[derive(Debug, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TlsConfig {
+    /// PEM file containing the certificate chain
+    pub cert: PathBuf,
+    /// PEM file containing the private key
+    pub key: PathBuf,
+    /// Generate a self-signed certificate at `cert` and `key` if they don't exist yet
+    #[serde(default)]
+    pub self_signed: bool,
+    /// Subject alternative names for a generated certificate
+    #[serde(default = "default_hostnames")]
+    pub hostnames: Vec<String>,
+}
+
+fn default_hostnames() -> Vec<String> {
+    vec!["localhost".to_string()]
+}
+
+#[derive(Debug, serde::Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct MdnsConfig {
+    /// Advertise the server as `_pixootop._tcp` for clients without a configured address
+    pub enabled: bool,
+    /// Name of the advertised instance, used by clients to pick one of several servers
+    pub instance_name: String,
+}
+
+impl Default for MdnsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            instance_name: "pixootop".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, serde::Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PrivacyConfig {
+    /// Light a pixel in `corner` on top of everything else while the camera or microphone is in
+    /// use
+    pub enabled: bool,
+    pub corner: Corner,
+    /// Metric which is above zero while the camera is in use
+    pub camera: String,
+    /// Metric which is above zero while the microphone is in use
+    pub microphone: String,
+}
+
+impl Default for PrivacyConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            corner: Corner::TopRight,
+            camera: "camera_in_use".to_string(),
+            microphone: "mic_in_use".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Corner {
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    BottomRight,
+}
+
+impl Config {
+    /// Loads the config from the path given as the first command line argument, or uses the
+    /// defaults if there is none.
+    pub fn load() -> Result<Self> {
+        let Some(path) = std::env::args_os().nth(1) else {
+            return Ok(Self::default());
+        };
+        let src = fs::read_to_string(&path)
+            .with_context(|| format!("reading config file {}", path.display()))?;
+        toml::from_str(&src).context("parsing config file")
+    }
+}
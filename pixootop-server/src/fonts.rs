@@ -94,6 +94,251 @@ pub static FONT_3X5: phf::Map<char, &[&[bool]]> = phf_map! {
         &[true],
         &[true],
     ],
+    '.' => &[
+        &[false],
+        &[false],
+        &[false],
+        &[false],
+        &[true],
+    ],
+    'K' => &[
+        &[true, false, true],
+        &[true, false, true],
+        &[true, true, false],
+        &[true, false, true],
+        &[true, false, true],
+    ],
+    'M' => &[
+        &[true, false, true],
+        &[true, true, true],
+        &[true, false, true],
+        &[true, false, true],
+        &[true, false, true],
+    ],
+    'G' => &[
+        &[true, true, true],
+        &[true, false, false],
+        &[true, false, true],
+        &[true, false, true],
+        &[true, true, true],
+    ],
+    'A' => &[
+        &[true, true, true],
+        &[true, false, true],
+        &[true, true, true],
+        &[true, false, true],
+        &[true, false, true],
+    ],
+    'B' => &[
+        &[true, true, false],
+        &[true, false, true],
+        &[true, true, false],
+        &[true, false, true],
+        &[true, true, false],
+    ],
+    'C' => &[
+        &[true, true, true],
+        &[true, false, false],
+        &[true, false, false],
+        &[true, false, false],
+        &[true, true, true],
+    ],
+    'D' => &[
+        &[true, true, false],
+        &[true, false, true],
+        &[true, false, true],
+        &[true, false, true],
+        &[true, true, false],
+    ],
+    'E' => &[
+        &[true, true, true],
+        &[true, false, false],
+        &[true, true, false],
+        &[true, false, false],
+        &[true, true, true],
+    ],
+    'F' => &[
+        &[true, true, true],
+        &[true, false, false],
+        &[true, true, false],
+        &[true, false, false],
+        &[true, false, false],
+    ],
+    'H' => &[
+        &[true, false, true],
+        &[true, false, true],
+        &[true, true, true],
+        &[true, false, true],
+        &[true, false, true],
+    ],
+    'J' => &[
+        &[false, false, true],
+        &[false, false, true],
+        &[false, false, true],
+        &[true, false, true],
+        &[true, true, true],
+    ],
+    'L' => &[
+        &[true, false, false],
+        &[true, false, false],
+        &[true, false, false],
+        &[true, false, false],
+        &[true, true, true],
+    ],
+    'N' => &[
+        &[true, true, false],
+        &[true, false, true],
+        &[true, false, true],
+        &[true, false, true],
+        &[true, false, true],
+    ],
+    'O' => &[
+        &[true, true, true],
+        &[true, false, true],
+        &[true, false, true],
+        &[true, false, true],
+        &[true, true, true],
+    ],
+    'P' => &[
+        &[true, true, true],
+        &[true, false, true],
+        &[true, true, true],
+        &[true, false, false],
+        &[true, false, false],
+    ],
+    'Q' => &[
+        &[true, true, true],
+        &[true, false, true],
+        &[true, false, true],
+        &[true, true, true],
+        &[false, false, true],
+    ],
+    'R' => &[
+        &[true, true, false],
+        &[true, false, true],
+        &[true, true, false],
+        &[true, false, true],
+        &[true, false, true],
+    ],
+    'S' => &[
+        &[true, true, true],
+        &[true, false, false],
+        &[true, true, true],
+        &[false, false, true],
+        &[true, true, true],
+    ],
+    'T' => &[
+        &[true, true, true],
+        &[false, true, false],
+        &[false, true, false],
+        &[false, true, false],
+        &[false, true, false],
+    ],
+    'U' => &[
+        &[true, false, true],
+        &[true, false, true],
+        &[true, false, true],
+        &[true, false, true],
+        &[true, true, true],
+    ],
+    'V' => &[
+        &[true, false, true],
+        &[true, false, true],
+        &[true, false, true],
+        &[true, false, true],
+        &[false, true, false],
+    ],
+    'W' => &[
+        &[true, false, true],
+        &[true, false, true],
+        &[true, false, true],
+        &[true, true, true],
+        &[true, false, true],
+    ],
+    'X' => &[
+        &[true, false, true],
+        &[true, false, true],
+        &[false, true, false],
+        &[true, false, true],
+        &[true, false, true],
+    ],
+    'Y' => &[
+        &[true, false, true],
+        &[true, false, true],
+        &[false, true, false],
+        &[false, true, false],
+        &[false, true, false],
+    ],
+    'Z' => &[
+        &[true, true, true],
+        &[false, false, true],
+        &[false, true, false],
+        &[true, false, false],
+        &[true, true, true],
+    ],
+    '-' => &[
+        &[false, false, false],
+        &[false, false, false],
+        &[true, true, true],
+        &[false, false, false],
+        &[false, false, false],
+    ],
+    '\'' => &[
+        &[true],
+        &[true],
+        &[false],
+        &[false],
+        &[false],
+    ],
+    '!' => &[
+        &[true],
+        &[true],
+        &[true],
+        &[false],
+        &[true],
+    ],
+    '?' => &[
+        &[true, true, true],
+        &[false, false, true],
+        &[false, true, true],
+        &[false, false, false],
+        &[false, true, false],
+    ],
+    '&' => &[
+        &[false, true, false],
+        &[true, false, true],
+        &[false, true, false],
+        &[true, false, true],
+        &[false, true, true],
+    ],
+    '/' => &[
+        &[false, false, true],
+        &[false, false, true],
+        &[false, true, false],
+        &[true, false, false],
+        &[true, false, false],
+    ],
+    ',' => &[
+        &[false],
+        &[false],
+        &[false],
+        &[true],
+        &[true],
+    ],
+    '(' => &[
+        &[false, true],
+        &[true, false],
+        &[true, false],
+        &[true, false],
+        &[false, true],
+    ],
+    ')' => &[
+        &[true, false],
+        &[false, true],
+        &[false, true],
+        &[false, true],
+        &[true, false],
+    ],
 };
 
 pub static FONT_3X4: phf::Map<char, &[&[bool]]> = phf_map! {
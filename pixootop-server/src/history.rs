@@ -0,0 +1,49 @@
+use std::collections::VecDeque;
+
+use crate::render::Context;
+
+/// The most recent client samples, ordered by time.
+pub struct History {
+    samples: VecDeque<Context>,
+    capacity: usize,
+}
+
+impl History {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            samples: VecDeque::with_capacity(capacity),
+            capacity,
+        }
+    }
+
+    pub fn push(&mut self, ctx: Context) {
+        if self.samples.back().is_some_and(|last| last.time > ctx.time) {
+            self.backfill(vec![ctx]);
+            return;
+        }
+        self.samples.push_back(ctx);
+        self.truncate();
+    }
+
+    /// Inserts samples the client buffered while the server was unreachable.
+    pub fn backfill(&mut self, samples: Vec<Context>) {
+        self.samples.extend(samples);
+        self.samples.make_contiguous().sort_by_key(|ctx| ctx.time);
+        self.truncate();
+    }
+
+    pub fn latest(&self) -> Option<&Context> {
+        self.samples.back()
+    }
+
+    /// All samples, newest first
+    pub fn newest_first(&self) -> impl Iterator<Item = &Context> {
+        self.samples.iter().rev()
+    }
+
+    fn truncate(&mut self) {
+        while self.samples.len() > self.capacity {
+            self.samples.pop_front();
+        }
+    }
+}
@@ -6,17 +6,20 @@ use std::{
 
 use actix_web::{
     App, HttpResponse, HttpServer, Responder, get, post,
-    web::{Data, Json, Path},
+    web::{Data, Json, JsonConfig, Path},
 };
 use anyhow::{Context as _, Result};
 use bluetooth_serial_port::BtAddr;
 use chrono::Local;
+use config::{Config, PrivacyConfig};
+use history::History;
 use image::DynamicImage;
 use log::{debug, error, info, trace};
 use pixoo::{
     Brightness, Pixoo,
     mode::{LightEffectMode, LightMode},
 };
+use popup::{Popup, PopupConfig};
 use render::Context;
 use tokio::{
     select,
@@ -24,12 +27,20 @@ use tokio::{
     time::{self, Instant},
 };
 use tokio_util::sync::CancellationToken;
+use widgets::Widget;
 
+mod config;
 mod fonts;
+mod history;
+mod mdns;
+mod popup;
 mod render;
+mod tls;
+mod widgets;
 
-const PIXOO_MAC_ADDR: &str = "11:75:58:35:2B:35";
 const PROGRESS_STEPS: u8 = 3;
+/// 10 minutes of samples sent every 100ms
+const HISTORY_LEN: usize = 6000;
 
 static BRIGHTNESS: AtomicU8 = AtomicU8::new(30);
 
@@ -43,9 +54,14 @@ enum Message {
     Brightness(Brightness),
 }
 
-async fn pixoo_loop(rx: &mut UnboundedReceiver<Message>) -> Result<()> {
-    let mut pixoo =
-        Pixoo::connect(BtAddr::from_str(PIXOO_MAC_ADDR).unwrap()).context("connecting to pixoo")?;
+enum StateMessage {
+    Update(Context),
+    Backfill(Vec<Context>),
+    Reset,
+}
+
+async fn pixoo_loop(mac_addr: BtAddr, rx: &mut UnboundedReceiver<Message>) -> Result<()> {
+    let mut pixoo = Pixoo::connect(mac_addr).context("connecting to pixoo")?;
     debug!("connected to Pixoo");
     pixoo
         .set_brightness(Brightness::new_saturating(
@@ -123,25 +139,47 @@ async fn pixoo_loop(rx: &mut UnboundedReceiver<Message>) -> Result<()> {
     }
 }
 
-async fn render_loop(mut rx: UnboundedReceiver<Option<Context>>, tx: UnboundedSender<Message>) {
-    let mut state = None;
+async fn render_loop(
+    mut rx: UnboundedReceiver<StateMessage>,
+    tx: UnboundedSender<Message>,
+    widgets: Vec<Widget>,
+    popup: PopupConfig,
+    privacy: PrivacyConfig,
+) {
+    let mut popup = Popup::new(popup);
+    let mut history = History::new(HISTORY_LEN);
+    let mut connected = false;
     let mut last_state_update = Instant::now();
     loop {
         time::sleep(Duration::from_millis(100)).await;
-        while let Ok(ctx) = rx.try_recv() {
-            state = ctx;
+        while let Ok(msg) = rx.try_recv() {
+            match msg {
+                StateMessage::Update(ctx) => {
+                    history.push(ctx);
+                    connected = true;
+                }
+                StateMessage::Backfill(samples) => {
+                    debug!("received {} buffered samples", samples.len());
+                    history.backfill(samples);
+                }
+                StateMessage::Reset => connected = false,
+            }
             last_state_update = Instant::now();
         }
 
         if last_state_update.elapsed() >= Duration::from_secs(60) {
-            if state.is_some() {
+            if connected {
                 info!("client disconnected");
             }
-            state = None;
+            connected = false;
         }
 
+        let history = connected.then_some(&history);
         let img = DynamicImage::from(render::create_frame(
-            state,
+            &widgets,
+            popup.update(history.and_then(History::latest)),
+            &privacy,
+            history,
             Local::now(),
             last_state_update.elapsed() >= Duration::from_secs(2),
         ));
@@ -149,7 +187,7 @@ async fn render_loop(mut rx: UnboundedReceiver<Option<Context>>, tx: UnboundedSe
     }
 }
 
-type AppData = Data<(UnboundedSender<Message>, UnboundedSender<Option<Context>>)>;
+type AppData = Data<(UnboundedSender<Message>, UnboundedSender<StateMessage>)>;
 
 #[post("/off")]
 async fn turn_off(data: AppData) -> impl Responder {
@@ -188,13 +226,19 @@ async fn get_brightness() -> impl Responder {
 
 #[post("/state")]
 async fn set_state(data: AppData, Json(body): Json<Context>) -> impl Responder {
-    _ = data.1.send(Some(body));
+    _ = data.1.send(StateMessage::Update(body));
+    HttpResponse::Ok()
+}
+
+#[post("/backfill")]
+async fn backfill(data: AppData, Json(body): Json<Vec<Context>>) -> impl Responder {
+    _ = data.1.send(StateMessage::Backfill(body));
     HttpResponse::Ok()
 }
 
 #[post("/reset-state")]
 async fn reset_state(data: AppData) -> impl Responder {
-    _ = data.1.send(None);
+    _ = data.1.send(StateMessage::Reset);
     HttpResponse::Ok()
 }
 
@@ -207,12 +251,22 @@ async fn index() -> impl Responder {
 async fn main() -> Result<()> {
     env_logger::init();
 
+    let config = Config::load()?;
+    let mac_addr = BtAddr::from_str(&config.pixoo_mac_addr)
+        .ok()
+        .context("invalid pixoo MAC address")?;
+
+    let mdns = match config.mdns.enabled {
+        true => Some(mdns::advertise(&config)?),
+        false => None,
+    };
+
     let cancel = CancellationToken::new();
 
     let (pixoo_tx, mut pixoo_rx) = mpsc::unbounded_channel();
     let cancel_clone = cancel.clone();
     let pixoo_job = tokio::spawn(async move {
-        while let Err(err) = pixoo_loop(&mut pixoo_rx).await {
+        while let Err(err) = pixoo_loop(mac_addr, &mut pixoo_rx).await {
             error!("pixoo service encountered error: {err:?}");
             if cancel_clone.is_cancelled() {
                 return;
@@ -223,17 +277,21 @@ async fn main() -> Result<()> {
     let (state_tx, state_rx) = mpsc::unbounded_channel();
     let pixoo_tx_2 = pixoo_tx.clone();
     let cancel_clone = cancel.clone();
+    let widgets = config.widgets.clone();
+    let popup = config.popup.clone();
+    let privacy = config.privacy.clone();
     let render_job = tokio::spawn(async move {
         select! {
-            _ = render_loop(state_rx, pixoo_tx_2) => {}
+            _ = render_loop(state_rx, pixoo_tx_2, widgets, popup, privacy) => {}
             _ = cancel_clone.cancelled() => {}
         }
     });
 
     let data = Data::new((pixoo_tx, state_tx));
-    HttpServer::new(move || {
+    let server = HttpServer::new(move || {
         App::new()
             .app_data(Data::clone(&data))
+            .app_data(JsonConfig::default().limit(4 << 20))
             .service(turn_off)
             .service(turn_on)
             .service(brightness_up)
@@ -241,14 +299,22 @@ async fn main() -> Result<()> {
             .service(set_brightness)
             .service(get_brightness)
             .service(set_state)
+            .service(backfill)
             .service(reset_state)
             .service(index)
-    })
-    .bind(("0.0.0.0", 6969))?
+    });
+    let addr = (config.bind_addr.as_str(), config.port);
+    match &config.tls {
+        Some(tls) => server.bind_rustls_0_23(addr, tls::server_config(tls)?)?,
+        None => server.bind(addr)?,
+    }
     .run()
     .await?;
 
     _ = tokio::signal::ctrl_c().await;
+    if let Some(mdns) = mdns {
+        _ = mdns.shutdown();
+    }
     cancel.cancel();
     pixoo_job.await.unwrap();
     render_job.await.unwrap();
@@ -0,0 +1,31 @@
+use anyhow::{Context as _, Result};
+use log::info;
+use mdns_sd::{ServiceDaemon, ServiceInfo};
+
+use crate::config::Config;
+
+pub const SERVICE_TYPE: &str = "_pixootop._tcp.local.";
+
+/// Advertises the server via DNS-SD. The service is withdrawn when the returned daemon is shut
+/// down or dropped.
+pub fn advertise(config: &Config) -> Result<ServiceDaemon> {
+    let mdns = ServiceDaemon::new().context("starting mDNS daemon")?;
+    let name = &config.mdns.instance_name;
+    let scheme = match config.tls {
+        Some(_) => "https",
+        None => "http",
+    };
+    let info = ServiceInfo::new(
+        SERVICE_TYPE,
+        name,
+        &format!("{name}.local."),
+        "",
+        config.port,
+        [("scheme", scheme)].as_slice(),
+    )
+    .context("creating mDNS service info")?
+    .enable_addr_auto();
+    mdns.register(info).context("registering mDNS service")?;
+    info!("advertising as {name:?} via mDNS");
+    Ok(mdns)
+}
@@ -0,0 +1,62 @@
+use std::time::{Duration, Instant};
+
+use crate::render::{Context, Metric};
+
+#[derive(Debug, Clone, serde::Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PopupConfig {
+    /// Show the level of `metric` in large for a moment when it changes, like a volume OSD
+    pub enabled: bool,
+    pub metric: String,
+    #[serde(with = "humantime_serde")]
+    pub duration: Duration,
+}
+
+impl Default for PopupConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            metric: "vol".to_string(),
+            duration: Duration::from_secs(1),
+        }
+    }
+}
+
+/// Tracks when the popup metric last changed.
+pub struct Popup {
+    config: PopupConfig,
+    /// Progress and mute state of the last sample
+    last: Option<(Option<u8>, bool)>,
+    until: Option<Instant>,
+}
+
+impl Popup {
+    pub fn new(config: PopupConfig) -> Self {
+        Self {
+            config,
+            last: None,
+            until: None,
+        }
+    }
+
+    /// Returns the metric to show in the popup, if it changed recently. The first sample after
+    /// connecting doesn't count as a change.
+    pub fn update<'a>(&mut self, ctx: Option<&'a Context>) -> Option<&'a Metric> {
+        if !self.config.enabled {
+            return None;
+        }
+        let Some(metric) = ctx.and_then(|ctx| ctx.metric(&self.config.metric)) else {
+            self.last = None;
+            return None;
+        };
+        let now = Instant::now();
+        let state = Some((metric.progress, metric.muted));
+        if self.last.is_some() && self.last != state {
+            self.until = Some(now + self.config.duration);
+        }
+        self.last = state;
+        self.until
+            .is_some_and(|until| now < until)
+            .then_some(metric)
+    }
+}
@@ -2,131 +2,852 @@ use chrono::{DateTime, Local};
 use image::{GenericImage, Rgb, RgbImage};
 use pixoo::DISPLAY_SIZE;
 
-use crate::{PROGRESS_STEPS, fonts};
+use crate::{
+    PROGRESS_STEPS,
+    config::{Corner, PrivacyConfig},
+    fonts,
+    history::History,
+    widgets::Widget,
+};
 
-#[derive(Debug, Clone, Copy, serde::Deserialize)]
+/// Progress of a full bar
+const PROGRESS_RANGE: f64 = PROGRESS_STEPS as f64 * 15.;
+/// Marks the bar of a muted audio device
+const MUTED_COLOR: Rgb<u8> = Rgb([0xff, 0x00, 0x00]);
+/// Colors of a status light while everything is fine and while something failed
+const STATUS_OK_COLOR: Rgb<u8> = Rgb([0x00, 0xaa, 0x00]);
+const STATUS_FAILED_COLOR: Rgb<u8> = Rgb([0xff, 0x00, 0x00]);
+/// Colors of the privacy indicator while the camera, or only the microphone, is in use
+const CAMERA_COLOR: Rgb<u8> = Rgb([0xff, 0x00, 0x00]);
+const MICROPHONE_COLOR: Rgb<u8> = Rgb([0xff, 0x88, 0x00]);
+
+#[derive(Debug, Clone, serde::Deserialize)]
 pub struct Context {
-    pub cpu: u8,
-    pub mem: u8,
-    pub gpu: u8,
-    pub gpu_mem: u8,
-    pub vol: u8,
-    pub net_up: u8,
-    pub net_down: u8,
+    pub metrics: Vec<Metric>,
     pub time: DateTime<Local>,
 }
 
-pub fn create_frame(ctx: Option<Context>, mut time: DateTime<Local>, stale: bool) -> RgbImage {
-    let mut img = RgbImage::new(DISPLAY_SIZE, DISPLAY_SIZE);
+#[derive(Debug, Clone, serde::Deserialize)]
+pub struct Metric {
+    pub name: String,
+    pub color: Option<[u8; 3]>,
+    /// In the unit the client sends along, like bytes per second
+    pub value: Option<f64>,
+    pub progress: Option<u8>,
+    /// Only sent for audio devices
+    #[serde(default)]
+    pub muted: bool,
+    /// Only sent for batteries
+    #[serde(default)]
+    pub charging: bool,
+    /// Only sent for media players
+    pub playing: Option<bool>,
+    pub text: Option<String>,
+    /// Progress of each part, like the bands of an audio spectrum or the cores of a CPU
+    pub parts: Option<Vec<u8>>,
+}
+
+impl Metric {
+    /// Whether this metric is called `name`, or is one of several devices of the metric `name`
+    fn is(&self, name: &str) -> bool {
+        self.name == name
+            || self
+                .name
+                .strip_prefix(name)
+                .is_some_and(|rest| rest.starts_with('.'))
+    }
+
+    fn colors(&self) -> [Rgb<u8>; 3] {
+        self.color.map_or_else(|| palette(&self.name), shades)
+    }
+}
+
+impl Context {
+    pub fn metric(&self, name: &str) -> Option<&Metric> {
+        self.metrics.iter().find(|metric| metric.is(name))
+    }
+}
 
+pub fn create_frame(
+    widgets: &[Widget],
+    popup: Option<&Metric>,
+    privacy: &PrivacyConfig,
+    history: Option<&History>,
+    mut time: DateTime<Local>,
+    stale: bool,
+) -> RgbImage {
+    let mut img = RgbImage::new(DISPLAY_SIZE, DISPLAY_SIZE);
+    let ctx = history.and_then(History::latest);
     if let Some(ctx) = ctx {
         time = ctx.time;
-        draw_progress(
-            ctx.vol,
-            &mut img,
-            0,
-            [
-                Rgb([0xff, 0xaa, 0x00]),
-                Rgb([0xbb, 0x88, 0x00]),
-                Rgb([0x88, 0x55, 0x00]),
-            ],
-            stale,
-        );
-        draw_progress(
-            ctx.mem,
-            &mut img,
-            1,
-            [
-                Rgb([0x00, 0xdd, 0x00]),
-                Rgb([0x00, 0x99, 0x00]),
-                Rgb([0x00, 0x55, 0x00]),
-            ],
-            stale,
-        );
-        draw_progress(
-            ctx.cpu,
-            &mut img,
-            2,
-            [
-                Rgb([0x00, 0x00, 0xdd]),
-                Rgb([0x00, 0x00, 0x99]),
-                Rgb([0x00, 0x00, 0x55]),
-            ],
-            stale,
-        );
-        draw_progress(
-            ctx.gpu,
-            &mut img,
-            3,
-            [
-                Rgb([0xff, 0x77, 0x00]),
-                Rgb([0xbb, 0x55, 0x00]),
-                Rgb([0x88, 0x33, 0x00]),
-            ],
-            stale,
-        );
-        draw_progress(
-            ctx.gpu_mem,
-            &mut img,
-            4,
-            [
-                Rgb([0xdd, 0x00, 0x00]),
-                Rgb([0x99, 0x00, 0x00]),
-                Rgb([0x55, 0x00, 0x00]),
-            ],
-            stale,
-        );
-        draw_progress(
-            ctx.net_up,
-            &mut img,
-            5,
-            [
-                Rgb([0x00, 0x99, 0xff]),
-                Rgb([0x00, 0x77, 0xbb]),
-                Rgb([0x00, 0x55, 0x88]),
-            ],
-            stale,
-        );
-        draw_progress(
-            ctx.net_down,
-            &mut img,
-            6,
-            [
-                Rgb([0x99, 0x00, 0xff]),
-                Rgb([0x66, 0x00, 0x99]),
-                Rgb([0x44, 0x00, 0x66]),
-            ],
-            stale,
+    }
+
+    for widget in widgets {
+        match widget {
+            Widget::Bars { y, rows, metrics } => {
+                let Some(ctx) = ctx else {
+                    continue;
+                };
+                let shown: Vec<&Metric> = match metrics {
+                    Some(names) => names
+                        .iter()
+                        .flat_map(|name| ctx.metrics.iter().filter(|metric| metric.is(name)))
+                        .collect(),
+                    None => ctx.metrics.iter().collect(),
+                };
+                for (row, metric) in shown.into_iter().take(*rows as usize).enumerate() {
+                    // a muted device keeps its volume, so show it dimmed with a red end
+                    draw_progress(
+                        metric.progress,
+                        &mut img,
+                        y + row as u32,
+                        metric.colors(),
+                        stale || metric.muted,
+                    );
+                    if metric.muted {
+                        put_clipped(
+                            &mut img,
+                            DISPLAY_SIZE - 1,
+                            y + row as u32,
+                            Some(MUTED_COLOR),
+                        );
+                    }
+                }
+            }
+            Widget::Graph {
+                metric,
+                x,
+                y,
+                width,
+                height,
+                step,
+            } => {
+                let (Some(history), Some(colors)) = (
+                    history,
+                    ctx.and_then(|ctx| Some(ctx.metric(metric)?.colors())),
+                ) else {
+                    continue;
+                };
+                let step = (*step).max(1);
+                // only the samples shown, not the whole history
+                let samples = history
+                    .newest_first()
+                    .take(*width as usize * step)
+                    .collect::<Vec<_>>();
+                for (column, chunk) in samples.chunks(step).enumerate() {
+                    let Some(progress) = chunk
+                        .iter()
+                        .filter_map(|ctx| ctx.metric(metric)?.progress)
+                        .max()
+                    else {
+                        continue;
+                    };
+                    let filled = ((progress as f64 / PROGRESS_RANGE).min(1.) * *height as f64)
+                        .round() as u32;
+                    let px = x + width - 1 - column as u32;
+                    for py in (y + height - filled)..(y + height) {
+                        if px < DISPLAY_SIZE && py < DISPLAY_SIZE {
+                            img.put_pixel(px, py, if stale { colors[2] } else { colors[0] });
+                        }
+                    }
+                }
+            }
+            Widget::Value { metric, x, y } => {
+                let Some((value, colors)) = ctx
+                    .and_then(|ctx| ctx.metric(metric))
+                    .and_then(|metric| Some((metric.value?, metric.colors())))
+                else {
+                    continue;
+                };
+                write_string(
+                    &format_value(value),
+                    &mut img,
+                    (*x, *y),
+                    &[if stale { colors[2] } else { colors[0] }],
+                    &fonts::FONT_3X5,
+                    true,
+                );
+            }
+            Widget::NowPlaying {
+                metric,
+                x,
+                y,
+                width,
+            } => {
+                let Some(metric) = ctx.and_then(|ctx| ctx.metric(metric)) else {
+                    continue;
+                };
+                draw_now_playing(&mut img, metric, time, (*x, *y), *width, stale);
+            }
+            Widget::Spectrum {
+                metric,
+                x,
+                y,
+                width,
+                height,
+                hold,
+            } => {
+                let (Some(history), Some(metric)) =
+                    (history, ctx.and_then(|ctx| ctx.metric(metric)))
+                else {
+                    continue;
+                };
+                draw_spectrum(
+                    &mut img,
+                    history,
+                    metric,
+                    (*x, *y, *width, *height),
+                    *hold,
+                    stale,
+                );
+            }
+            Widget::Heatmap {
+                metric,
+                x,
+                y,
+                columns,
+                rows,
+                cell_size,
+            } => {
+                let Some(parts) = ctx.and_then(|ctx| ctx.metric(metric)?.parts.as_ref()) else {
+                    continue;
+                };
+                let cells = (columns * rows) as usize;
+                if parts.is_empty() || cells == 0 {
+                    continue;
+                }
+                // spread the parts evenly, so every cell gets one or two of 17 cores on 16
+                // cells instead of two each and the last cells none
+                let cells = cells.min(parts.len());
+                for cell in 0..cells {
+                    let chunk =
+                        &parts[cell * parts.len() / cells..(cell + 1) * parts.len() / cells];
+                    let progress = chunk.iter().copied().max().unwrap_or(0);
+                    let color = heat(progress as f64 / PROGRESS_RANGE, stale);
+                    let cell_x = x.saturating_add(cell as u32 % columns * cell_size);
+                    let cell_y = y.saturating_add(cell as u32 / columns * cell_size);
+                    for px in cell_x..cell_x.saturating_add(*cell_size).min(DISPLAY_SIZE) {
+                        for py in cell_y..cell_y.saturating_add(*cell_size).min(DISPLAY_SIZE) {
+                            put_clipped(&mut img, px, py, Some(color));
+                        }
+                    }
+                }
+            }
+            Widget::Stacked {
+                metric,
+                x,
+                y,
+                width,
+            } => {
+                let Some(metric) = ctx.and_then(|ctx| ctx.metric(metric)) else {
+                    continue;
+                };
+                let Some(parts) = &metric.parts else {
+                    continue;
+                };
+                let colors = metric.colors();
+                let mut end = 0.;
+                let mut column = 0;
+                // later parts are a shade darker, which must not look like a stale first part
+                let stale_rest = Rgb(colors[2].0.map(|c| c / 2));
+                for (i, &part) in parts.iter().enumerate() {
+                    end += (part as f64 / PROGRESS_RANGE) * *width as f64;
+                    let color = match (stale, i) {
+                        (false, 0) => colors[0],
+                        (false, _) => colors[1],
+                        (true, 0) => colors[2],
+                        (true, _) => stale_rest,
+                    };
+                    while column < *width && (column as f64) < end.round() {
+                        put_clipped(&mut img, x + column, *y, Some(color));
+                        column += 1;
+                    }
+                }
+            }
+            Widget::Battery {
+                metric,
+                x,
+                y,
+                width,
+                low,
+            } => {
+                let Some(metric) = ctx.and_then(|ctx| ctx.metric(metric)) else {
+                    continue;
+                };
+                draw_battery(&mut img, metric, time, (*x, *y), *width, *low, stale);
+            }
+            Widget::Text {
+                metric,
+                x,
+                y,
+                width,
+                threshold,
+            } => {
+                let Some(metric) = ctx.and_then(|ctx| ctx.metric(metric)) else {
+                    continue;
+                };
+                let (Some(text), Some(value)) = (&metric.text, metric.value) else {
+                    continue;
+                };
+                if value < *threshold {
+                    continue;
+                }
+                let colors = metric.colors();
+                let color = match stale {
+                    true => colors[2],
+                    false => colors[0],
+                };
+                draw_scrolling(&mut img, text, color, (*x, *y), *width, time);
+            }
+            Widget::Clock { x, y } => draw_clock(&mut img, time, (*x, *y)),
+            Widget::Heat {
+                metric,
+                x,
+                y,
+                width,
+                height,
+            } => {
+                let Some(progress) = ctx.and_then(|ctx| ctx.metric(metric)?.progress) else {
+                    continue;
+                };
+                let color = heat(progress as f64 / PROGRESS_RANGE, stale);
+                for px in *x..x.saturating_add(*width).min(DISPLAY_SIZE) {
+                    for py in *y..y.saturating_add(*height).min(DISPLAY_SIZE) {
+                        img.put_pixel(px, py, color);
+                    }
+                }
+            }
+            Widget::Status {
+                metrics,
+                x,
+                y,
+                width,
+                height,
+                threshold,
+            } => {
+                let Some(ctx) = ctx else {
+                    continue;
+                };
+                let values = ctx
+                    .metrics
+                    .iter()
+                    .filter(|metric| metrics.iter().any(|name| metric.is(name)))
+                    .filter_map(|metric| metric.value)
+                    .collect::<Vec<_>>();
+                if values.is_empty() {
+                    continue;
+                }
+                let colors = match values.iter().any(|value| value > threshold) {
+                    true => shades(STATUS_FAILED_COLOR.0),
+                    false => shades(STATUS_OK_COLOR.0),
+                };
+                let color = if stale { colors[2] } else { colors[0] };
+                for px in *x..x.saturating_add(*width).min(DISPLAY_SIZE) {
+                    for py in *y..y.saturating_add(*height).min(DISPLAY_SIZE) {
+                        put_clipped(&mut img, px, py, Some(color));
+                    }
+                }
+            }
+        }
+    }
+
+    if let Some(metric) = popup {
+        draw_popup(&mut img, metric);
+    }
+
+    if let Some(ctx) = ctx
+        && privacy.enabled
+    {
+        draw_privacy(&mut img, ctx, privacy, stale);
+    }
+
+    img
+}
+
+/// Lights a pixel in a corner while the camera or microphone is in use. As it is drawn last, it
+/// shows regardless of the widgets and the popup.
+fn draw_privacy(img: &mut RgbImage, ctx: &Context, config: &PrivacyConfig, stale: bool) {
+    let in_use = |name: &str| {
+        ctx.metrics
+            .iter()
+            .filter(|metric| metric.is(name))
+            .any(|metric| metric.value.is_some_and(|value| value > 0.))
+    };
+    let color = match (in_use(&config.camera), in_use(&config.microphone)) {
+        (true, _) => CAMERA_COLOR,
+        (false, true) => MICROPHONE_COLOR,
+        (false, false) => return,
+    };
+    let (x, y) = match config.corner {
+        Corner::TopLeft => (0, 0),
+        Corner::TopRight => (DISPLAY_SIZE - 1, 0),
+        Corner::BottomLeft => (0, DISPLAY_SIZE - 1),
+        Corner::BottomRight => (DISPLAY_SIZE - 1, DISPLAY_SIZE - 1),
+    };
+    // dimmed like the bars, as the camera may have been turned off since
+    let colors = shades(color.0);
+    img.put_pixel(x, y, if stale { colors[2] } else { colors[0] });
+}
+
+/// Covers the display with the value of a metric in large and a thick bar below it.
+fn draw_popup(img: &mut RgbImage, metric: &Metric) {
+    let colors = metric.colors();
+    let color = match metric.muted {
+        true => MUTED_COLOR,
+        false => colors[0],
+    };
+    for pixel in img.pixels_mut() {
+        *pixel = Rgb([0, 0, 0]);
+    }
+    if let Some(value) = metric.value {
+        let text = format!("{value:.0}");
+        // 3 pixels per digit with a space in between
+        let width = text.len() as u32 * 4 - 1;
+        write_string(
+            &text,
+            img,
+            (DISPLAY_SIZE.saturating_sub(width) / 2, 3),
+            &[color],
+            &fonts::FONT_3X5,
+            true,
         );
     }
+    let filled = metric.progress.map_or(0., |progress| {
+        (progress as f64 / PROGRESS_RANGE).min(1.) * DISPLAY_SIZE as f64
+    });
+    for x in 0..DISPLAY_SIZE {
+        let color = match (x as f64) < filled.round() {
+            true => color,
+            false => Rgb(colors[2].0.map(|c| c / 3)),
+        };
+        for y in 10..13 {
+            img.put_pixel(x, y, color);
+        }
+    }
+}
 
+fn draw_clock<I: GenericImage<Pixel = Rgb<u8>>>(
+    img: &mut I,
+    time: DateTime<Local>,
+    (x, y): (u32, u32),
+) {
     write_string(
         &time.format("%l:%M").to_string(),
-        &mut img,
-        (3, 7),
+        img,
+        (x, y),
         &[Rgb([0xff, 0x00, 0xff]), Rgb([0xff, 0x00, 0x99])],
         &fonts::FONT_3X5,
         false,
     );
     write_string(
         &time.format("%S").to_string(),
-        &mut img,
-        (10, 12),
+        img,
+        (x + 7, y + 5),
         &[Rgb([0x88, 0x00, 0x88]), Rgb([0x88, 0x00, 0x55])],
         &fonts::FONT_3X4,
         false,
     );
+}
 
-    img
+/// Pixels the title scrolls per second
+const SCROLL_SPEED: i64 = 8;
+
+fn draw_now_playing(
+    img: &mut RgbImage,
+    metric: &Metric,
+    time: DateTime<Local>,
+    (x, y): (u32, u32),
+    width: u32,
+    stale: bool,
+) {
+    let (Some(playing), Some(text)) = (metric.playing, &metric.text) else {
+        return;
+    };
+    let colors = metric.colors();
+    let color = match stale {
+        true => colors[2],
+        false => colors[0],
+    };
+    let glyph: [[bool; 3]; 5] = match playing {
+        true => [
+            [true, false, false],
+            [true, true, false],
+            [true, true, true],
+            [true, true, false],
+            [true, false, false],
+        ],
+        false => [[true, false, true]; 5],
+    };
+    for (gy, row) in glyph.iter().enumerate() {
+        for (gx, &px) in row.iter().enumerate() {
+            put_clipped(img, x + gx as u32, y + gy as u32, px.then_some(color));
+        }
+    }
+
+    draw_scrolling(img, text, color, (x + 4, y), width.saturating_sub(4), time);
+
+    let filled = metric.progress.map_or(0., |progress| {
+        (progress as f64 / PROGRESS_RANGE).min(1.) * width as f64
+    });
+    for column in 0..width {
+        let color = match (column as f64) < filled.round() {
+            true => color,
+            false => colors[2],
+        };
+        put_clipped(img, x + column, y + 6, Some(color));
+    }
+}
+
+fn draw_spectrum(
+    img: &mut RgbImage,
+    history: &History,
+    metric: &Metric,
+    (x, y, width, height): (u32, u32, u32, u32),
+    hold: usize,
+    stale: bool,
+) {
+    let Some(bands) = &metric.parts else {
+        return;
+    };
+    if bands.is_empty() {
+        return;
+    }
+    let colors = metric.colors();
+    let (bar, peak) = match stale {
+        true => (colors[2], colors[2]),
+        false => (colors[1], colors[0]),
+    };
+    let rows =
+        |progress: u8| ((progress as f64 / PROGRESS_RANGE).min(1.) * height as f64).round() as u32;
+    // the highest value of each band within the last `hold` samples
+    let mut peaks = bands.clone();
+    for ctx in history.newest_first().take(hold) {
+        let Some(old) = ctx
+            .metric(&metric.name)
+            .and_then(|metric| metric.parts.as_ref())
+        else {
+            continue;
+        };
+        for (peak, &old) in peaks.iter_mut().zip(old) {
+            *peak = (*peak).max(old);
+        }
+    }
+
+    let bottom = y.saturating_add(height);
+    for px in x..x.saturating_add(width).min(DISPLAY_SIZE) {
+        for py in y..bottom.min(DISPLAY_SIZE) {
+            put_clipped(img, px, py, Some(Rgb([0, 0, 0])));
+        }
+    }
+    for column in 0..width.min(DISPLAY_SIZE.saturating_sub(x)) {
+        let band = (column as usize * bands.len() / width as usize).min(bands.len() - 1);
+        let filled = rows(bands[band]);
+        // rows below the display are skipped
+        for row in bottom.saturating_sub(DISPLAY_SIZE)..filled {
+            put_clipped(img, x + column, bottom - 1 - row, Some(bar));
+        }
+        let peak_row = rows(peaks[band]);
+        if peak_row > 0 {
+            put_clipped(img, x + column, bottom - peak_row, Some(peak));
+        }
+    }
+}
+
+/// Writes `text` into `width` columns, scrolling it with a gap between repetitions if it
+/// doesn't fit.
+fn draw_scrolling(
+    img: &mut RgbImage,
+    text: &str,
+    color: Rgb<u8>,
+    (x, y): (u32, u32),
+    width: u32,
+    time: DateTime<Local>,
+) {
+    const GAP: u32 = 4;
+    // render the text once, then copy the visible part of it
+    let text = text.to_uppercase();
+    let text_width = text
+        .chars()
+        .filter_map(|char| fonts::FONT_3X5.get(&char))
+        .map(|char| char[0].len() as u32 + 1)
+        .sum::<u32>();
+    let mut rendered = RgbImage::new(text_width.max(1), 5);
+    write_string(
+        &text,
+        &mut rendered,
+        (0, 0),
+        &[color],
+        &fonts::FONT_3X5,
+        true,
+    );
+    let scrolling = text_width > width;
+    let offset = match scrolling {
+        true => (time.timestamp_millis() * SCROLL_SPEED / 1000)
+            .rem_euclid((text_width + GAP) as i64) as u32,
+        false => 0,
+    };
+    for column in 0..width {
+        let source = match scrolling {
+            true => (offset + column) % (text_width + GAP),
+            false => column,
+        };
+        if source >= text_width {
+            continue;
+        }
+        for row in 0..5 {
+            let pixel = *rendered.get_pixel(source, row);
+            put_clipped(
+                img,
+                x + column,
+                y + row,
+                (pixel != Rgb([0, 0, 0])).then_some(pixel),
+            );
+        }
+    }
+}
+
+/// Pixels the charging light moves per second
+const CHARGING_SPEED: i64 = 10;
+
+fn draw_battery(
+    img: &mut RgbImage,
+    metric: &Metric,
+    time: DateTime<Local>,
+    (x, y): (u32, u32),
+    width: u32,
+    low: f64,
+    stale: bool,
+) {
+    let (Some(value), Some(progress)) = (metric.value, metric.progress) else {
+        return;
+    };
+    if width == 0 {
+        return;
+    }
+    let colors = match value < low && !metric.charging {
+        true => shades([0xff, 0x00, 0x00]),
+        false => metric.colors(),
+    };
+    let filled = (((progress as f64 / PROGRESS_RANGE).min(1.) * width as f64).round() as u32)
+        .max(1)
+        .min(width);
+    let light = match metric.charging && !stale {
+        true => {
+            Some((time.timestamp_millis() * CHARGING_SPEED / 1000).rem_euclid(filled as i64) as u32)
+        }
+        false => None,
+    };
+    for column in 0..filled {
+        let color = match (stale, light == Some(column)) {
+            (true, _) => colors[2],
+            (false, true) => Rgb([0xff, 0xff, 0xff]),
+            (false, false) => colors[0],
+        };
+        put_clipped(img, x + column, y, Some(color));
+    }
+}
+
+/// Sets a pixel if there is one and it is on the display.
+fn put_clipped(img: &mut RgbImage, x: u32, y: u32, pixel: Option<Rgb<u8>>) {
+    if let Some(pixel) = pixel
+        && x < img.width()
+        && y < img.height()
+    {
+        img.put_pixel(x, y, pixel);
+    }
+}
+
+/// Formats a value with at most two significant digits and an SI suffix, like `12M`, `1.5K`, or
+/// `4.2G` for a CPU frequency, to fit the display.
+fn format_value(value: f64) -> String {
+    let (value, suffix) = [(1e9, "G"), (1e6, "M"), (1e3, "K")]
+        .into_iter()
+        // values which round up to the factor, like 999.7 to `1.0K` instead of `1000`
+        .find(|(factor, _)| value.abs() >= factor * 0.9995)
+        .map_or((value, ""), |(factor, suffix)| (value / factor, suffix));
+    match value.abs() < 9.95 && !suffix.is_empty() {
+        true => format!("{value:.1}{suffix}"),
+        false => format!("{value:.0}{suffix}"),
+    }
+}
+
+/// Color for a fraction of a full bar, going from blue over green and yellow to red
+fn heat(fraction: f64, stale: bool) -> Rgb<u8> {
+    const STOPS: [[f64; 3]; 4] = [
+        [0., 0., 221.],
+        [0., 221., 0.],
+        [221., 221., 0.],
+        [221., 0., 0.],
+    ];
+    let pos = fraction.clamp(0., 1.) * (STOPS.len() - 1) as f64;
+    let i = (pos.floor() as usize).min(STOPS.len() - 2);
+    let t = pos - i as f64;
+    let factor = match stale {
+        true => 0.4,
+        false => 1.,
+    };
+    Rgb(std::array::from_fn(|c| {
+        ((STOPS[i][c] + (STOPS[i + 1][c] - STOPS[i][c]) * t) * factor).round() as u8
+    }))
+}
+
+/// Derives colors for a progress bar from a single configured color
+fn shades(color: [u8; 3]) -> [Rgb<u8>; 3] {
+    let shade = |factor: f64| Rgb(color.map(|c| (c as f64 * factor).round() as u8));
+    [Rgb(color), shade(0.7), shade(0.4)]
+}
+
+/// Colors for a progress bar: full steps, two thirds of a step, and one third of a step. Metrics
+/// for one of several devices, like `gpu.card1`, use the color of their base metric.
+fn palette(metric: &str) -> [Rgb<u8>; 3] {
+    match metric.split('.').next().unwrap_or(metric) {
+        "vol" => [
+            Rgb([0xff, 0xaa, 0x00]),
+            Rgb([0xbb, 0x88, 0x00]),
+            Rgb([0x88, 0x55, 0x00]),
+        ],
+        "mic" => [
+            Rgb([0x00, 0xdd, 0xdd]),
+            Rgb([0x00, 0x99, 0x99]),
+            Rgb([0x00, 0x55, 0x55]),
+        ],
+        "media" => [
+            Rgb([0x00, 0xdd, 0x66]),
+            Rgb([0x00, 0x99, 0x44]),
+            Rgb([0x00, 0x55, 0x22]),
+        ],
+        "cpu_freq" => [
+            Rgb([0x66, 0x66, 0xff]),
+            Rgb([0x44, 0x44, 0xbb]),
+            Rgb([0x22, 0x22, 0x77]),
+        ],
+        "load" | "procs" | "procs_running" | "threads" | "threads_running" => [
+            Rgb([0xaa, 0x00, 0xdd]),
+            Rgb([0x77, 0x00, 0x99]),
+            Rgb([0x44, 0x00, 0x55]),
+        ],
+        "mem" => [
+            Rgb([0x00, 0xdd, 0x00]),
+            Rgb([0x00, 0x99, 0x00]),
+            Rgb([0x00, 0x55, 0x00]),
+        ],
+        "cpu" => [
+            Rgb([0x00, 0x00, 0xdd]),
+            Rgb([0x00, 0x00, 0x99]),
+            Rgb([0x00, 0x00, 0x55]),
+        ],
+        "gpu" => [
+            Rgb([0xff, 0x77, 0x00]),
+            Rgb([0xbb, 0x55, 0x00]),
+            Rgb([0x88, 0x33, 0x00]),
+        ],
+        "gpu_mem" => [
+            Rgb([0xdd, 0x00, 0x00]),
+            Rgb([0x99, 0x00, 0x00]),
+            Rgb([0x55, 0x00, 0x00]),
+        ],
+        "disk_read" => [
+            Rgb([0x00, 0xdd, 0xaa]),
+            Rgb([0x00, 0x99, 0x77]),
+            Rgb([0x00, 0x55, 0x44]),
+        ],
+        "disk_write" => [
+            Rgb([0xdd, 0x00, 0x88]),
+            Rgb([0x99, 0x00, 0x66]),
+            Rgb([0x55, 0x00, 0x33]),
+        ],
+        "fs" => [
+            Rgb([0xdd, 0xdd, 0x00]),
+            Rgb([0x99, 0x99, 0x00]),
+            Rgb([0x55, 0x55, 0x00]),
+        ],
+        "spectrum" => [
+            Rgb([0x00, 0xff, 0x99]),
+            Rgb([0x00, 0x99, 0x55]),
+            Rgb([0x00, 0x55, 0x33]),
+        ],
+        "swap" => [
+            Rgb([0x99, 0xdd, 0x00]),
+            Rgb([0x66, 0x99, 0x00]),
+            Rgb([0x33, 0x55, 0x00]),
+        ],
+        "psi_cpu" | "psi_mem" | "psi_io" => [
+            Rgb([0xff, 0x33, 0x33]),
+            Rgb([0xbb, 0x22, 0x22]),
+            Rgb([0x77, 0x11, 0x11]),
+        ],
+        "battery" | "battery_time" | "battery_power" => [
+            Rgb([0x00, 0xdd, 0x00]),
+            Rgb([0x00, 0x99, 0x00]),
+            Rgb([0x00, 0x55, 0x00]),
+        ],
+        "top_cpu" | "top_mem" => [
+            Rgb([0xff, 0x66, 0x00]),
+            Rgb([0xbb, 0x44, 0x00]),
+            Rgb([0x77, 0x22, 0x00]),
+        ],
+        "cgroup_cpu" | "cgroup_mem" | "cgroup_io" => [
+            Rgb([0x00, 0xaa, 0xaa]),
+            Rgb([0x00, 0x77, 0x77]),
+            Rgb([0x00, 0x44, 0x44]),
+        ],
+        "failed_units" | "journal_errors" => [
+            Rgb([0xff, 0x00, 0x44]),
+            Rgb([0xbb, 0x00, 0x33]),
+            Rgb([0x77, 0x00, 0x22]),
+        ],
+        "camera_in_use" | "mic_in_use" => [
+            Rgb([0xff, 0x44, 0x00]),
+            Rgb([0xbb, 0x33, 0x00]),
+            Rgb([0x77, 0x22, 0x00]),
+        ],
+        "net_up" => [
+            Rgb([0x00, 0x99, 0xff]),
+            Rgb([0x00, 0x77, 0xbb]),
+            Rgb([0x00, 0x55, 0x88]),
+        ],
+        "net_down" => [
+            Rgb([0x99, 0x00, 0xff]),
+            Rgb([0x66, 0x00, 0x99]),
+            Rgb([0x44, 0x00, 0x66]),
+        ],
+        "temp" => [
+            Rgb([0xff, 0x33, 0x88]),
+            Rgb([0xbb, 0x22, 0x66]),
+            Rgb([0x77, 0x11, 0x44]),
+        ],
+        "fan" => [
+            Rgb([0x66, 0xcc, 0xff]),
+            Rgb([0x44, 0x88, 0xbb]),
+            Rgb([0x22, 0x55, 0x77]),
+        ],
+        "power" | "gpu_power" => [
+            Rgb([0xff, 0xcc, 0x00]),
+            Rgb([0xbb, 0x99, 0x00]),
+            Rgb([0x77, 0x66, 0x00]),
+        ],
+        _ => [
+            Rgb([0xdd, 0xdd, 0xdd]),
+            Rgb([0x99, 0x99, 0x99]),
+            Rgb([0x55, 0x55, 0x55]),
+        ],
+    }
 }
 
 fn draw_progress<I: GenericImage>(
-    progress: u8,
+    progress: Option<u8>,
     img: &mut I,
     y: u32,
     colors: [I::Pixel; 3],
     stale: bool,
 ) {
+    let Some(progress) = progress else {
+        return;
+    };
+    // configured bars may start below the display's last row
+    if y >= img.height() {
+        return;
+    }
     let full = (progress / PROGRESS_STEPS).min(15);
     let rest = match full >= 15 {
         true => 0,
@@ -135,6 +856,10 @@ fn draw_progress<I: GenericImage>(
     for x in 0..=full {
         img.put_pixel(x as u32, y, if stale { colors[2] } else { colors[0] });
     }
+    // a full bar has no room for a partial step
+    if full >= 15 {
+        return;
+    }
     match (rest, stale) {
         (1, _) | (_, true) => img.put_pixel(full as u32 + 1, y, colors[2]),
         (2, false) => img.put_pixel(full as u32 + 1, y, colors[1]),
@@ -158,8 +883,10 @@ fn write_string<I: GenericImage>(
 
         for (cy, &line) in char.iter().enumerate() {
             for (cx, &px) in line.iter().enumerate() {
-                if px {
-                    img.put_pixel(x + cx as u32, y + cy as u32, colors[i]);
+                let (px_x, px_y) = (x + cx as u32, y + cy as u32);
+                // configured widgets may not fit on the display
+                if px && px_x < img.width() && px_y < img.height() {
+                    img.put_pixel(px_x, px_y, colors[i]);
                 }
             }
         }
@@ -169,3 +896,240 @@ fn write_string<I: GenericImage>(
         i %= colors.len();
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use chrono::Local;
+
+    use super::*;
+
+    fn metric(name: &str, progress: u8) -> Metric {
+        Metric {
+            name: name.to_string(),
+            color: None,
+            value: Some(progress as f64),
+            progress: Some(progress),
+            muted: false,
+            charging: false,
+            playing: None,
+            text: None,
+            parts: None,
+        }
+    }
+
+    fn history(metrics: Vec<Metric>) -> History {
+        let mut history = History::new(10);
+        history.push(Context {
+            metrics,
+            time: Local::now(),
+        });
+        history
+    }
+
+    #[test]
+    fn draws_full_muted_bar() {
+        let widgets = [Widget::Bars {
+            y: 0,
+            rows: 7,
+            metrics: None,
+        }];
+        let mut vol = metric("vol", u8::MAX);
+        vol.muted = true;
+        let history = history(vec![vol]);
+        let img = create_frame(
+            &widgets,
+            None,
+            &PrivacyConfig::default(),
+            Some(&history),
+            Local::now(),
+            false,
+        );
+        assert_eq!(*img.get_pixel(DISPLAY_SIZE - 1, 0), MUTED_COLOR);
+    }
+
+    #[test]
+    fn spreads_heatmap_parts_over_all_cells() {
+        let widgets = [Widget::Heatmap {
+            metric: "cpu".to_string(),
+            x: 0,
+            y: 0,
+            columns: 4,
+            rows: 4,
+            cell_size: 1,
+        }];
+        let mut cpu = metric("cpu", 0);
+        cpu.parts = Some(vec![0; 17]);
+        let history = history(vec![cpu]);
+        let img = create_frame(
+            &widgets,
+            None,
+            &PrivacyConfig::default(),
+            Some(&history),
+            Local::now(),
+            false,
+        );
+        for cell in 0..16 {
+            assert_eq!(
+                *img.get_pixel(cell % 4, cell / 4),
+                heat(0., false),
+                "{cell}"
+            );
+        }
+    }
+
+    #[test]
+    fn formats_values() {
+        for (value, formatted) in [
+            (0., "0"),
+            (5.3, "5"),
+            (42., "42"),
+            (999.4, "999"),
+            (999.7, "1.0K"),
+            (1500., "1.5K"),
+            (9960., "10K"),
+            (12e6, "12M"),
+            (4.2e9, "4.2G"),
+            (-2500., "-2.5K"),
+        ] {
+            assert_eq!(format_value(value), formatted, "{value}");
+        }
+    }
+
+    #[test]
+    fn draws_stacked_parts_in_distinct_shades() {
+        let widgets = [Widget::Stacked {
+            metric: "mem".to_string(),
+            x: 0,
+            y: 0,
+            width: 16,
+        }];
+        let mut mem = metric("mem", 0);
+        mem.parts = Some(vec![PROGRESS_RANGE as u8 / 2, PROGRESS_RANGE as u8 / 4]);
+        let colors = mem.colors();
+        let history = history(vec![mem]);
+        for stale in [false, true] {
+            let img = create_frame(
+                &widgets,
+                None,
+                &PrivacyConfig::default(),
+                Some(&history),
+                Local::now(),
+                stale,
+            );
+            let (used, cache) = (*img.get_pixel(0, 0), *img.get_pixel(10, 0));
+            assert_ne!(used, cache);
+            assert_eq!(used, colors[if stale { 2 } else { 0 }]);
+            assert_ne!(cache, colors[2]);
+            assert_eq!(*img.get_pixel(15, 0), Rgb([0, 0, 0]));
+        }
+    }
+
+    #[test]
+    fn clips_status_light_at_the_edge() {
+        let widgets = [Widget::Status {
+            metrics: vec!["failed_units".to_string()],
+            x: 15,
+            y: 15,
+            width: u32::MAX,
+            height: u32::MAX,
+            threshold: 0.,
+        }];
+        let history = history(vec![metric("failed_units", 1)]);
+        for stale in [false, true] {
+            let img = create_frame(
+                &widgets,
+                None,
+                &PrivacyConfig::default(),
+                Some(&history),
+                Local::now(),
+                stale,
+            );
+            let colors = shades(STATUS_FAILED_COLOR.0);
+            assert_eq!(*img.get_pixel(15, 15), colors[if stale { 2 } else { 0 }]);
+        }
+    }
+
+    #[test]
+    fn clips_heat_and_spectrum_at_the_edge() {
+        let widgets = [
+            Widget::Heat {
+                metric: "cpu".to_string(),
+                x: 15,
+                y: 0,
+                width: u32::MAX,
+                height: u32::MAX,
+            },
+            Widget::Spectrum {
+                metric: "spectrum".to_string(),
+                x: 14,
+                y: 14,
+                width: u32::MAX,
+                height: 2,
+                hold: 1,
+            },
+            Widget::Heatmap {
+                metric: "cpu".to_string(),
+                x: 0,
+                y: 15,
+                columns: 2,
+                rows: 1,
+                cell_size: u32::MAX,
+            },
+        ];
+        let mut cpu = metric("cpu", 0);
+        cpu.parts = Some(vec![0, 0]);
+        let mut spectrum = metric("spectrum", 0);
+        spectrum.parts = Some(vec![u8::MAX]);
+        let history = history(vec![cpu, spectrum]);
+        let img = create_frame(
+            &widgets,
+            None,
+            &PrivacyConfig::default(),
+            Some(&history),
+            Local::now(),
+            false,
+        );
+        assert_eq!(*img.get_pixel(15, 0), heat(0., false));
+        assert_eq!(*img.get_pixel(14, 14), palette("spectrum")[0]);
+        assert_eq!(*img.get_pixel(15, 15), heat(0., false));
+    }
+
+    #[test]
+    fn dims_privacy_light_when_stale() {
+        let history = history(vec![metric("camera_in_use", 1), metric("mic_in_use", 1)]);
+        for stale in [false, true] {
+            let img = create_frame(
+                &[],
+                None,
+                &PrivacyConfig::default(),
+                Some(&history),
+                Local::now(),
+                stale,
+            );
+            let colors = shades(CAMERA_COLOR.0);
+            assert_eq!(
+                *img.get_pixel(DISPLAY_SIZE - 1, 0),
+                colors[if stale { 2 } else { 0 }]
+            );
+        }
+    }
+
+    #[test]
+    fn clips_bars_below_the_display() {
+        let widgets = [Widget::Bars {
+            y: 10,
+            rows: 7,
+            metrics: None,
+        }];
+        let history = history((0..7).map(|i| metric(&format!("m{i}"), 20)).collect());
+        let img = create_frame(
+            &widgets,
+            None,
+            &PrivacyConfig::default(),
+            Some(&history),
+            Local::now(),
+            false,
+        );
+        assert_ne!(*img.get_pixel(0, 15), Rgb([0, 0, 0]));
+    }
+}
@@ -0,0 +1,71 @@
+use std::{
+    fs::{self, OpenOptions},
+    io::{BufReader, Write as _},
+    os::unix::fs::OpenOptionsExt as _,
+};
+
+use anyhow::{Context as _, Result, bail};
+use log::info;
+use rustls::ServerConfig;
+
+use crate::config::TlsConfig;
+
+pub fn server_config(config: &TlsConfig) -> Result<ServerConfig> {
+    if config.self_signed && !config.cert.exists() && !config.key.exists() {
+        generate_self_signed(config)?;
+    }
+
+    let certs = rustls_pemfile::certs(&mut BufReader::new(
+        fs::File::open(&config.cert).context("opening certificate file")?,
+    ))
+    .collect::<Result<Vec<_>, _>>()
+    .context("reading certificate file")?;
+    if certs.is_empty() {
+        bail!("no certificates found in {}", config.cert.display());
+    }
+    info!(
+        "certificate SHA-256 fingerprint: {}",
+        fingerprint(&certs[0])
+    );
+    let key = rustls_pemfile::private_key(&mut BufReader::new(
+        fs::File::open(&config.key).context("opening private key file")?,
+    ))
+    .context("reading private key file")?
+    .with_context(|| format!("no private key found in {}", config.key.display()))?;
+
+    ServerConfig::builder_with_provider(rustls::crypto::ring::default_provider().into())
+        .with_safe_default_protocol_versions()
+        .context("configuring TLS protocol versions")?
+        .with_no_client_auth()
+        .with_single_cert(certs, key)
+        .context("configuring TLS certificate")
+}
+
+fn generate_self_signed(config: &TlsConfig) -> Result<()> {
+    info!(
+        "generating self-signed certificate for {:?}",
+        config.hostnames
+    );
+    let cert = rcgen::generate_simple_self_signed(config.hostnames.clone())
+        .context("generating self-signed certificate")?;
+    fs::write(&config.cert, cert.cert.pem()).context("writing certificate file")?;
+    OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .mode(0o600)
+        .open(&config.key)
+        .and_then(|mut file| file.write_all(cert.key_pair.serialize_pem().as_bytes()))
+        .context("writing private key file")?;
+    Ok(())
+}
+
+/// Formats the SHA-256 hash of a DER encoded certificate as colon separated hex, like
+/// `openssl x509 -fingerprint -sha256` does.
+fn fingerprint(der: &[u8]) -> String {
+    ring::digest::digest(&ring::digest::SHA256, der)
+        .as_ref()
+        .iter()
+        .map(|b| format!("{b:02X}"))
+        .collect::<Vec<_>>()
+        .join(":")
+}
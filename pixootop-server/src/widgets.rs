@@ -0,0 +1,230 @@
+/// Something drawn on the display. Widgets are drawn in order, so later ones are drawn on top
+/// of earlier ones.
+#[derive(Debug, Clone, serde::Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+pub enum Widget {
+    /// A progress bar per metric, one row each
+    Bars {
+        /// Row of the first bar
+        #[serde(default)]
+        y: u32,
+        #[serde(default = "default_bar_rows")]
+        rows: u32,
+        /// Names of the metrics to show in this order, all metrics in the order the client
+        /// sends them if not set. A base name like `gpu` also matches `gpu.card1`.
+        metrics: Option<Vec<String>>,
+    },
+    /// The current time
+    Clock {
+        #[serde(default = "default_clock_x")]
+        x: u32,
+        #[serde(default = "default_clock_y")]
+        y: u32,
+    },
+    /// The recent values of a metric as columns, newest on the right
+    Graph {
+        metric: String,
+        #[serde(default)]
+        x: u32,
+        #[serde(default)]
+        y: u32,
+        #[serde(default = "default_graph_width")]
+        width: u32,
+        #[serde(default = "default_bar_rows")]
+        height: u32,
+        /// Number of samples combined into one column, using the highest of them
+        #[serde(default = "default_graph_step")]
+        step: usize,
+    },
+    /// The value of a metric as a number like `12M`, in the metric's color
+    Value { metric: String, x: u32, y: u32 },
+    /// A play or pause symbol, the scrolling title of the playing track, and its position
+    /// below, 7 rows in total. Nothing is drawn while no player is running.
+    NowPlaying {
+        #[serde(default = "default_now_playing_metric")]
+        metric: String,
+        #[serde(default)]
+        x: u32,
+        #[serde(default)]
+        y: u32,
+        #[serde(default = "default_graph_width")]
+        width: u32,
+    },
+    /// Bars for the frequency bands of an audio spectrum with dots for their recent peaks. It
+    /// covers its area only while audio is playing, so it can be put over other widgets.
+    Spectrum {
+        #[serde(default = "default_spectrum_metric")]
+        metric: String,
+        #[serde(default)]
+        x: u32,
+        #[serde(default)]
+        y: u32,
+        #[serde(default = "default_graph_width")]
+        width: u32,
+        #[serde(default = "default_spectrum_height")]
+        height: u32,
+        /// Number of samples a peak is held for
+        #[serde(default = "default_spectrum_hold")]
+        hold: usize,
+    },
+    /// A grid of cells colored like `heat` by the parts of a metric, like the cores of a CPU.
+    /// Cells are filled row by row, combining several parts into one cell by their highest value
+    /// if there are more parts than cells.
+    Heatmap {
+        #[serde(default = "default_heatmap_metric")]
+        metric: String,
+        x: u32,
+        y: u32,
+        #[serde(default = "default_heatmap_cells")]
+        columns: u32,
+        #[serde(default = "default_heatmap_cells")]
+        rows: u32,
+        /// Size of a cell in pixels
+        #[serde(default = "default_size")]
+        cell_size: u32,
+    },
+    /// A bar of the parts of a metric one after another, the first in the metric's color and the
+    /// rest in a darker shade, like used memory followed by the page cache
+    Stacked {
+        #[serde(default = "default_stacked_metric")]
+        metric: String,
+        #[serde(default)]
+        x: u32,
+        y: u32,
+        #[serde(default = "default_graph_width")]
+        width: u32,
+    },
+    /// A bar for the charge of a battery, which turns red when low and shows a light running
+    /// along it while charging
+    Battery {
+        #[serde(default = "default_battery_metric")]
+        metric: String,
+        #[serde(default)]
+        x: u32,
+        y: u32,
+        #[serde(default = "default_graph_width")]
+        width: u32,
+        /// Percentage below which the battery counts as low
+        #[serde(default = "default_battery_low")]
+        low: f64,
+    },
+    /// The scrolling text of a metric, like the name of the busiest process, in the metric's
+    /// color. Nothing is drawn while the metric's value is below `threshold`.
+    Text {
+        metric: String,
+        #[serde(default)]
+        x: u32,
+        y: u32,
+        #[serde(default = "default_graph_width")]
+        width: u32,
+        #[serde(default)]
+        threshold: f64,
+    },
+    /// A light which turns red while any of `metrics` is above `threshold`, like failed systemd
+    /// units, and is green otherwise
+    Status {
+        #[serde(default = "default_status_metrics")]
+        metrics: Vec<String>,
+        x: u32,
+        y: u32,
+        #[serde(default = "default_size")]
+        width: u32,
+        #[serde(default = "default_size")]
+        height: u32,
+        #[serde(default)]
+        threshold: f64,
+    },
+    /// A rectangle colored from blue to red by how full a metric's bar would be, e.g. for
+    /// temperatures
+    Heat {
+        metric: String,
+        x: u32,
+        y: u32,
+        #[serde(default = "default_size")]
+        width: u32,
+        #[serde(default = "default_size")]
+        height: u32,
+    },
+}
+
+fn default_bar_rows() -> u32 {
+    7
+}
+
+fn default_graph_width() -> u32 {
+    16
+}
+
+/// One second per column with the client's default send interval
+fn default_graph_step() -> usize {
+    10
+}
+
+fn default_now_playing_metric() -> String {
+    "media".to_string()
+}
+
+fn default_spectrum_metric() -> String {
+    "spectrum".to_string()
+}
+
+/// The whole display, as the spectrum is meant to replace the dashboard while audio plays
+fn default_spectrum_height() -> u32 {
+    16
+}
+
+/// One second with the client's default send interval
+fn default_spectrum_hold() -> usize {
+    10
+}
+
+fn default_heatmap_metric() -> String {
+    "cpu".to_string()
+}
+
+fn default_heatmap_cells() -> u32 {
+    4
+}
+
+fn default_stacked_metric() -> String {
+    "mem".to_string()
+}
+
+fn default_battery_metric() -> String {
+    "battery".to_string()
+}
+
+fn default_battery_low() -> f64 {
+    20.
+}
+
+fn default_clock_x() -> u32 {
+    3
+}
+
+fn default_clock_y() -> u32 {
+    7
+}
+
+fn default_size() -> u32 {
+    1
+}
+
+/// Bars for all metrics above the clock, like before widgets were configurable.
+pub fn default_widgets() -> Vec<Widget> {
+    vec![
+        Widget::Bars {
+            y: 0,
+            rows: default_bar_rows(),
+            metrics: None,
+        },
+        Widget::Clock {
+            x: default_clock_x(),
+            y: default_clock_y(),
+        },
+    ]
+}
+
+fn default_status_metrics() -> Vec<String> {
+    vec!["failed_units".to_string()]
+}